@@ -0,0 +1,169 @@
+//! Calibration helpers.
+//!
+//! These methods measure the sensor response in a known situation and compute
+//! the corresponding offsets or references. The results can be persisted and
+//! applied again later with `apply_calibration()`.
+
+use hal::{delay::DelayNs, i2c};
+use {Apds9960, Error, LightData};
+
+/// Time to wait after changing the configuration before taking a measurement.
+const SETTLE_TIME_MS: u32 = 50;
+
+/// Step of the calibration procedure done by `calibrate_all()`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CalibrationStep {
+    /// Remove any object in front of the sensor.
+    /// The proximity offsets will be calibrated.
+    ProximityOffset,
+    /// Cover the sensor with a flat target parallel to it.
+    /// The gesture offsets will be calibrated.
+    GestureOffset,
+    /// Place a white target in front of the sensor.
+    /// A white reference reading will be taken.
+    WhiteBalance,
+}
+
+/// Complete calibration results.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct CalibrationBlob {
+    /// Proximity up/right photodiode offset.
+    pub proximity_up_right_offset: i8,
+    /// Proximity down/left photodiode offset.
+    pub proximity_down_left_offset: i8,
+    /// Gesture offsets in the order: up, down, left, right.
+    pub gesture_offsets: [i8; 4],
+    /// White reference reading, if the white balance step was done.
+    pub white_reference: Option<LightData>,
+}
+
+/// Calibration.
+impl<I2C, E> Apds9960<I2C>
+where
+    I2C: i2c::I2c<Error = E>,
+{
+    /// Calibrate the proximity offsets.
+    ///
+    /// There must be no object in front of the sensor and the proximity engine
+    /// must be enabled. The offsets are reset, the remaining proximity reading
+    /// (crosstalk) is measured and used as offset for both photodiode pairs.
+    ///
+    /// Returns the configured offset.
+    pub fn calibrate_proximity_offsets<D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+    ) -> Result<i8, Error<E>> {
+        self.set_proximity_offsets(0, 0)?;
+        delay.delay_ms(SETTLE_TIME_MS);
+        let crosstalk = self.poll_with_delay(delay, Self::read_proximity)?;
+        let offset = core::cmp::min(crosstalk, 127) as i8;
+        self.set_proximity_offsets(offset, offset)?;
+        Ok(offset)
+    }
+
+    /// Calibrate the gesture offsets.
+    ///
+    /// The sensor must be covered with a flat target parallel to it and the
+    /// gesture engine must be enabled. The offsets are reset, gesture mode is
+    /// forced for one measurement and the offsets are set so that the four
+    /// photodiodes report the same value.
+    ///
+    /// Returns the configured offsets in the order: up, down, left, right.
+    pub fn calibrate_gesture_offsets<D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+    ) -> Result<[i8; 4], Error<E>> {
+        self.set_gesture_offsets(0, 0, 0, 0)?;
+        self.enable_gesture_mode()?;
+        delay.delay_ms(SETTLE_TIME_MS);
+        let mut buffer = [0; 128];
+        let result = self.poll_with_delay(delay, |dev| {
+            if !dev.is_gesture_data_valid().map_err(nb::Error::Other)? {
+                return Err(nb::Error::WouldBlock);
+            }
+            dev.read_gesture_fifo_all(&mut buffer)
+                .map_err(nb::Error::Other)
+        });
+        self.disable_gesture_mode()?;
+        let byte_count = result?;
+
+        let mut sums = [0u32; 4];
+        let dataset_count = byte_count / 4;
+        for dataset in buffer[..dataset_count * 4].chunks_exact(4) {
+            for (sum, value) in sums.iter_mut().zip(dataset) {
+                *sum += u32::from(*value);
+            }
+        }
+        let mut offsets = [0i8; 4];
+        if dataset_count > 0 {
+            let mean = sums.iter().sum::<u32>() as i32 / (4 * dataset_count as i32);
+            for (offset, sum) in offsets.iter_mut().zip(sums.iter()) {
+                let average = *sum as i32 / dataset_count as i32;
+                *offset = (average - mean).clamp(-127, 127) as i8;
+            }
+        }
+        self.set_gesture_offsets(offsets[0], offsets[1], offsets[2], offsets[3])?;
+        Ok(offsets)
+    }
+
+    /// Take a white reference reading of the color sensor.
+    ///
+    /// A white target must be placed in front of the sensor and the color /
+    /// ambient light engine must be enabled.
+    pub fn read_white_reference<D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+    ) -> Result<LightData, Error<E>> {
+        delay.delay_ms(SETTLE_TIME_MS);
+        self.poll_with_delay(delay, Self::read_light)
+    }
+
+    /// Run a guided calibration of the proximity offsets, the gesture offsets
+    /// and the white balance.
+    ///
+    /// Before each step the `step` callback is called so that the user can be
+    /// prompted to prepare the setup (see [`CalibrationStep`]). The callback
+    /// should return once the user has confirmed, with `true` to perform the step
+    /// or `false` to skip it. Skipped steps leave the corresponding fields of the
+    /// result at their default value.
+    ///
+    /// The engines used by each step must be enabled.
+    ///
+    /// [`CalibrationStep`]: enum.CalibrationStep.html
+    pub fn calibrate_all<D, F>(
+        &mut self,
+        delay: &mut D,
+        mut step: F,
+    ) -> Result<CalibrationBlob, Error<E>>
+    where
+        D: DelayNs,
+        F: FnMut(CalibrationStep) -> bool,
+    {
+        let mut blob = CalibrationBlob::default();
+        if step(CalibrationStep::ProximityOffset) {
+            let offset = self.calibrate_proximity_offsets(delay)?;
+            blob.proximity_up_right_offset = offset;
+            blob.proximity_down_left_offset = offset;
+        }
+        if step(CalibrationStep::GestureOffset) {
+            blob.gesture_offsets = self.calibrate_gesture_offsets(delay)?;
+        }
+        if step(CalibrationStep::WhiteBalance) {
+            blob.white_reference = Some(self.read_white_reference(delay)?);
+        }
+        Ok(blob)
+    }
+
+    /// Apply previously obtained calibration results.
+    ///
+    /// This writes the proximity and gesture offsets. The white reference is
+    /// only used for color processing and is not written to the device.
+    pub fn apply_calibration(&mut self, blob: &CalibrationBlob) -> Result<(), Error<E>> {
+        self.set_proximity_offsets(
+            blob.proximity_up_right_offset,
+            blob.proximity_down_left_offset,
+        )?;
+        let offsets = blob.gesture_offsets;
+        self.set_gesture_offsets(offsets[0], offsets[1], offsets[2], offsets[3])
+    }
+}
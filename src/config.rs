@@ -1,20 +1,268 @@
-use hal::i2c;
+use hal::{delay::DelayNs, i2c};
 use {
-    register::{Config1, Enable},
-    Apds9960, BitFlags, Error, Register, DEV_ADDR,
+    defaults,
+    register::{Config1, Config2, Config3, Enable, GConfig1, GConfig4, Pers},
+    Apds9960, BitFlags, Error, ReadMode, Register, DEV_ADDR,
 };
 
 macro_rules! impl_set_flag_reg {
     ($method:ident, $reg:ident) => {
+        // The cached value is updated by `write_register()`.
         pub(crate) fn $method(&mut self, flag: u8, value: bool) -> Result<(), Error<E>> {
             let new = self.$reg.with(flag, value);
-            self.config_register(&new)?;
-            self.$reg = new;
-            Ok(())
+            self.config_register(&new)
         }
     };
 }
 
+/// Registers whose values are cached by the driver and read back by `refresh()`.
+const CACHED_REGISTERS: [u8; 7] = [
+    Register::ENABLE,
+    Register::PERS,
+    Register::CONFIG1,
+    Register::CONFIG2,
+    Register::CONFIG3,
+    Register::GCONF1,
+    Register::GCONFIG4,
+];
+
+/// Registers written by `init()`, and read back by `init_verified()`, in order.
+const INIT_REGISTERS: [(u8, u8); 27] = [
+    // Proximity
+    (Register::PPULSE, defaults::PPULSE),
+    (Register::POFFSET_UR, defaults::POFFSET_UR),
+    (Register::POFFSET_DL, defaults::POFFSET_DL),
+    (Register::PILT, defaults::PILT),
+    (Register::PIHT, defaults::PIHT),
+    // Light
+    (Register::ATIME, defaults::ATIME),
+    (Register::AILTL, defaults::AILT as u8),
+    (Register::AILTL + 1, (defaults::AILT >> 8) as u8),
+    (Register::AIHTL, defaults::AIHT as u8),
+    (Register::AIHTL + 1, (defaults::AIHT >> 8) as u8),
+    // Persistence, wait time and configuration
+    (Register::PERS, defaults::PERS),
+    (Register::WTIME, defaults::WTIME),
+    (Register::CONFIG1, defaults::CONFIG1),
+    (Register::CONFIG2, defaults::CONFIG2),
+    (Register::CONFIG3, defaults::CONFIG3),
+    // Gesture
+    (Register::GPENTH, defaults::GPENTH),
+    (Register::GEXTH, defaults::GEXTH),
+    (Register::GCONF1, defaults::GCONF1),
+    (Register::GCONF2, defaults::GCONF2),
+    (Register::GPULSE, defaults::GPULSE),
+    (Register::GCONF3, defaults::GCONF3),
+    (Register::GCONFIG4, defaults::GCONFIG4),
+    // Gesture offsets
+    (Register::GOFFSET_U, defaults::GOFFSET),
+    (Register::GOFFSET_D, defaults::GOFFSET),
+    (Register::GOFFSET_L, defaults::GOFFSET),
+    (Register::GOFFSET_R, defaults::GOFFSET),
+    // Gains and LED drive
+    (Register::CONTROL, defaults::CONTROL),
+];
+
+/// Power-on reset values from the datasheet written by `reset_to_defaults()`, in order.
+const POR_REGISTERS: [(u8, u8); 28] = [
+    (Register::ENABLE, 0),
+    (Register::ATIME, 0xFF),
+    (Register::WTIME, 0xFF),
+    (Register::AILTL, 0),
+    (Register::AILTL + 1, 0),
+    (Register::AIHTL, 0),
+    (Register::AIHTL + 1, 0),
+    (Register::PILT, 0),
+    (Register::PIHT, 0),
+    (Register::PERS, 0),
+    (Register::CONFIG1, 0x40),
+    (Register::PPULSE, 0x40),
+    (Register::CONTROL, 0),
+    (Register::CONFIG2, 0x01),
+    (Register::POFFSET_UR, 0),
+    (Register::POFFSET_DL, 0),
+    (Register::CONFIG3, 0),
+    (Register::GPENTH, 0),
+    (Register::GEXTH, 0),
+    (Register::GCONF1, 0),
+    (Register::GCONF2, 0),
+    (Register::GOFFSET_U, 0),
+    (Register::GOFFSET_D, 0),
+    (Register::GPULSE, 0x40),
+    (Register::GOFFSET_L, 0),
+    (Register::GOFFSET_R, 0),
+    (Register::GCONF3, 0),
+    (Register::GCONFIG4, 0),
+];
+
+/// Combination of enabled functions (ENABLE register contents).
+///
+/// Flags can be combined with `|`:
+/// ```
+/// # use apds9960::Mode;
+/// let mode = Mode::POWER_ON | Mode::PROXIMITY | Mode::GESTURE;
+/// assert_eq!(0b0100_0101, u8::from(mode));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Mode(u8);
+
+impl Mode {
+    /// Power on (PON).
+    pub const POWER_ON: Mode = Mode(Enable::PON);
+    /// Color / ambient light detection (AEN).
+    pub const LIGHT: Mode = Mode(Enable::AEN);
+    /// Proximity detection (PEN).
+    pub const PROXIMITY: Mode = Mode(Enable::PEN);
+    /// Wait between cycles (WEN).
+    pub const WAIT: Mode = Mode(Enable::WEN);
+    /// Ambient light interrupt generation (AIEN).
+    pub const LIGHT_INTERRUPT: Mode = Mode(Enable::AIEN);
+    /// Proximity interrupt generation (PIEN).
+    pub const PROXIMITY_INTERRUPT: Mode = Mode(Enable::PIEN);
+    /// Gesture detection (GEN).
+    pub const GESTURE: Mode = Mode(Enable::GEN);
+
+    /// No function enabled.
+    pub fn empty() -> Self {
+        Mode(0)
+    }
+
+    /// Raw ENABLE register value.
+    pub fn bits(self) -> u8 {
+        self.0
+    }
+
+    /// Whether all the flags in `other` are also set in `self`.
+    pub fn contains(self, other: Mode) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl core::ops::BitOr for Mode {
+    type Output = Mode;
+    fn bitor(self, rhs: Mode) -> Mode {
+        Mode(self.0 | rhs.0)
+    }
+}
+
+impl core::ops::BitOrAssign for Mode {
+    fn bitor_assign(&mut self, rhs: Mode) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl From<u8> for Mode {
+    /// The reserved bit 7 is ignored.
+    fn from(value: u8) -> Self {
+        Mode(value & !0b1000_0000)
+    }
+}
+
+impl From<Mode> for u8 {
+    fn from(mode: Mode) -> Self {
+        mode.0
+    }
+}
+
+/// Decoded ENABLE register contents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct EnableFlags {
+    /// Power on (PON).
+    pub power: bool,
+    /// Color / ambient light detection (AEN).
+    pub als: bool,
+    /// Proximity detection (PEN).
+    pub proximity: bool,
+    /// Wait between cycles (WEN).
+    pub wait: bool,
+    /// Ambient light interrupt generation (AIEN).
+    pub als_interrupt: bool,
+    /// Proximity interrupt generation (PIEN).
+    pub proximity_interrupt: bool,
+    /// Gesture detection (GEN).
+    pub gesture: bool,
+}
+
+impl From<u8> for EnableFlags {
+    fn from(value: u8) -> Self {
+        EnableFlags {
+            power: value & Enable::PON != 0,
+            als: value & Enable::AEN != 0,
+            proximity: value & Enable::PEN != 0,
+            wait: value & Enable::WEN != 0,
+            als_interrupt: value & Enable::AIEN != 0,
+            proximity_interrupt: value & Enable::PIEN != 0,
+            gesture: value & Enable::GEN != 0,
+        }
+    }
+}
+
+/// Measurement engine or function that can be enabled in the ENABLE register.
+///
+/// See [`is_engine_enabled()`](struct.Apds9960.html#method.is_engine_enabled).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Engine {
+    /// Proximity detection (PEN).
+    Proximity,
+    /// Color / ambient light detection (AEN).
+    Als,
+    /// Gesture detection (GEN).
+    Gesture,
+    /// Wait between cycles (WEN).
+    Wait,
+}
+
+impl Engine {
+    fn mask(self) -> u8 {
+        match self {
+            Engine::Proximity => Enable::PEN,
+            Engine::Als => Enable::AEN,
+            Engine::Gesture => Enable::GEN,
+            Engine::Wait => Enable::WEN,
+        }
+    }
+}
+
+/// Snapshot of the device configuration registers.
+///
+/// See [`get_config()`](struct.Apds9960.html#method.get_config) and
+/// [`restore_config()`](struct.Apds9960.html#method.restore_config).
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ConfigSnapshot {
+    /// ENABLE register.
+    pub enable: u8,
+    /// ATIME register.
+    pub atime: u8,
+    /// WTIME register.
+    pub wtime: u8,
+    /// PERS register.
+    pub pers: u8,
+    /// CONFIG1 register.
+    pub config1: u8,
+    /// PPULSE register.
+    pub ppulse: u8,
+    /// CONTROL register.
+    pub control: u8,
+    /// CONFIG2 register.
+    pub config2: u8,
+    /// CONFIG3 register.
+    pub config3: u8,
+    /// GPENTH register.
+    pub gpenth: u8,
+    /// GEXTH register.
+    pub gexth: u8,
+    /// GCONF1 register.
+    pub gconf1: u8,
+    /// GCONF2 register.
+    pub gconf2: u8,
+    /// GPULSE register.
+    pub gpulse: u8,
+    /// GCONF3 register.
+    pub gconf3: u8,
+    /// GCONF4 register.
+    pub gconf4: u8,
+}
+
 /// Common configuration.
 impl<I2C, E> Apds9960<I2C>
 where
@@ -29,6 +277,7 @@ where
     /// - Gesture: 4x gain, 100mA LED, entry thresh 40, exit thresh 30
     ///
     /// After calling this, you should enable the specific engines you need.
+    /// The register values written are available in the [`defaults`](defaults/index.html) module.
     ///
     /// This function first probes the I2C bus to verify the device is present
     /// before attempting configuration.
@@ -39,53 +288,141 @@ where
         // Disable all features first
         self.disable()?;
 
-        // Set proximity defaults
-        self.write_register(Register::PPULSE, 0x87)?; // 16us, 8 pulses
-        self.write_register(Register::POFFSET_UR, 0)?;
-        self.write_register(Register::POFFSET_DL, 0)?;
-        self.write_register(Register::PILT, 0)?;
-        self.write_register(Register::PIHT, 50)?;
-
-        // Set light defaults
-        self.write_register(Register::ATIME, 0)?; // 256 cycles = 712ms
-        self.write_double_register(Register::AILTL, 0xFFFF)?;
-        self.write_double_register(Register::AIHTL, 0)?;
-
-        // Set persistence: 4 proximity cycles, 0 ALS cycles
-        self.write_register(Register::PERS, 0x40)?; // (4 << 4) | 0
+        for &(register, value) in INIT_REGISTERS.iter() {
+            self.write_register(register, value)?;
+        }
 
-        // Set wait time
-        self.write_register(Register::WTIME, 246)?; // 27ms
+        // Enable power
+        self.enable()
+    }
 
-        // CONFIG1: no 12x wait
-        self.write_register(Register::CONFIG1, 0x60)?;
+    /// Initialize the sensor like [`init()`](struct.Apds9960.html#method.init)
+    /// and read every register back after writing it.
+    ///
+    /// Returns `Error::VerifyFailed` as soon as a register does not contain the
+    /// value written, for example due to a flaky bus. This takes about twice as
+    /// many bus transactions as `init()`.
+    pub fn init_verified(&mut self) -> Result<(), Error<E>> {
+        self.probe()?;
+        self.disable()?;
+        for &(register, value) in INIT_REGISTERS.iter() {
+            self.write_register(register, value)?;
+            self.verify_register(register, value)?;
+        }
+        self.enable()?;
+        self.verify_register(Register::ENABLE, Enable::PON)
+    }
 
-        // CONFIG2: LED boost 100%, no saturation interrupts
-        self.write_register(Register::CONFIG2, 0x01)?;
+    /// Write the power-on reset values from the datasheet to every configurable register.
+    ///
+    /// Unlike [`init()`](struct.Apds9960.html#method.init), which applies the settings
+    /// chosen by this driver (see [`defaults`](defaults/index.html)), this leaves the
+    /// device configured as after power-up: powered off, with all engines and interrupts
+    /// disabled. The register values cached by the driver are reset as well.
+    pub fn reset_to_defaults(&mut self) -> Result<(), Error<E>> {
+        for &(register, value) in POR_REGISTERS.iter() {
+            self.write_register(register, value)?;
+        }
+        Ok(())
+    }
 
-        // CONFIG3: all photodiodes enabled
-        self.write_register(Register::CONFIG3, 0)?;
+    /// Reset the device to a clean state without power-cycling it.
+    ///
+    /// This disables all engines, clears all interrupts and the gesture FIFO,
+    /// waits for the device to settle and reads the register values cached
+    /// by the driver back from the device
+    /// (see [`refresh()`](struct.Apds9960.html#method.refresh)).
+    ///
+    /// The device is not probed again and the rest of the configuration
+    /// (thresholds, gains, etc.) is not changed. Use
+    /// [`init()`](struct.Apds9960.html#method.init) for that.
+    pub fn soft_reset<D: DelayNs>(&mut self, delay: &mut D) -> Result<(), Error<E>> {
+        self.disable()?;
+        self.clear_interrupts()?;
+        self.clear_gesture_fifo()?;
+        // Initialization time after entering / leaving sleep (5.7ms).
+        delay.delay_us(5700);
+        self.refresh()
+    }
 
-        // Set gesture defaults
-        self.write_register(Register::GPENTH, 40)?; // Entry threshold
-        self.write_register(Register::GEXTH, 30)?; // Exit threshold
-        self.write_register(Register::GCONF1, 0x40)?; // 4 events for int, 1 for exit
-        self.write_register(Register::GCONF2, 0x41)?; // 4x gain, 100mA, 2.8ms wait
-        self.write_register(Register::GPULSE, 0xC9)?; // 32us, 10 pulses
-        self.write_register(Register::GCONF3, 0)?; // All photodiodes active
-        self.write_register(Register::GCONFIG4, 0)?; // Gesture interrupts disabled initially
+    /// Power the device down cleanly.
+    ///
+    /// All engines are disabled, all interrupts and the gesture FIFO are cleared and
+    /// finally the device is powered off (PON cleared), which leaves it in its
+    /// lowest-power sleep state. This is the proper teardown sequence before
+    /// [`destroy()`](struct.Apds9960.html#method.destroy).
+    pub fn shutdown(&mut self) -> Result<(), Error<E>> {
+        self.set_mode_flags(Mode::POWER_ON)?;
+        self.clear_interrupts()?;
+        self.clear_gesture_fifo()?;
+        self.disable()
+    }
 
-        // Set gesture offsets to 0
-        self.write_register(Register::GOFFSET_U, 0)?;
-        self.write_register(Register::GOFFSET_D, 0)?;
-        self.write_register(Register::GOFFSET_L, 0)?;
-        self.write_register(Register::GOFFSET_R, 0)?;
+    /// Read the current configuration registers.
+    ///
+    /// The snapshot can be written back with
+    /// [`restore_config()`](struct.Apds9960.html#method.restore_config).
+    pub fn get_config(&mut self) -> Result<ConfigSnapshot, Error<E>> {
+        Ok(ConfigSnapshot {
+            enable: self.read_register(Register::ENABLE)?,
+            atime: self.read_register(Register::ATIME)?,
+            wtime: self.read_register(Register::WTIME)?,
+            pers: self.read_register(Register::PERS)?,
+            config1: self.read_register(Register::CONFIG1)?,
+            ppulse: self.read_register(Register::PPULSE)?,
+            control: self.read_register(Register::CONTROL)?,
+            config2: self.read_register(Register::CONFIG2)?,
+            config3: self.read_register(Register::CONFIG3)?,
+            gpenth: self.read_register(Register::GPENTH)?,
+            gexth: self.read_register(Register::GEXTH)?,
+            gconf1: self.read_register(Register::GCONF1)?,
+            gconf2: self.read_register(Register::GCONF2)?,
+            gpulse: self.read_register(Register::GPULSE)?,
+            gconf3: self.read_register(Register::GCONF3)?,
+            gconf4: self.read_register(Register::GCONFIG4)?,
+        })
+    }
 
-        // Set control register: 100mA LED, 4x proximity gain, 4x ALS gain
-        self.write_register(Register::CONTROL, 0x09)?; // (0 << 6) | (2 << 2) | 1
+    /// Write a configuration snapshot back to the device.
+    ///
+    /// The ENABLE register is written last so that the engines are only
+    /// started once the rest of the configuration is in place.
+    pub fn restore_config(&mut self, snap: &ConfigSnapshot) -> Result<(), Error<E>> {
+        self.write_register(Register::ATIME, snap.atime)?;
+        self.write_register(Register::WTIME, snap.wtime)?;
+        self.config_register(&Pers::create(snap.pers))?;
+        self.config_register(&Config1::create(snap.config1))?;
+        self.write_register(Register::PPULSE, snap.ppulse)?;
+        self.write_register(Register::CONTROL, snap.control)?;
+        self.config_register(&Config2::create(snap.config2))?;
+        self.config_register(&Config3::create(snap.config3))?;
+        self.write_register(Register::GPENTH, snap.gpenth)?;
+        self.write_register(Register::GEXTH, snap.gexth)?;
+        self.config_register(&GConfig1::create(snap.gconf1))?;
+        self.write_register(Register::GCONF2, snap.gconf2)?;
+        self.write_register(Register::GPULSE, snap.gpulse)?;
+        self.write_register(Register::GCONF3, snap.gconf3)?;
+        // Do not trigger a FIFO clear when restoring.
+        self.config_register(&GConfig4::create(snap.gconf4).with(GConfig4::GFIFO_CLR, false))?;
+        self.config_register(&Enable::create(snap.enable))?;
+        Ok(())
+    }
 
-        // Enable power
-        self.enable()
+    /// Read the registers cached by the driver back from the device.
+    ///
+    /// The driver keeps a copy of the ENABLE, PERS, CONFIG1, CONFIG2, CONFIG3, GCONF1
+    /// and GCONF4 registers so that single flags can be changed without reading the
+    /// register first. Every write done through this driver keeps these copies up
+    /// to date, but they get out of sync if the device is changed by other means,
+    /// for example by another driver instance on the same device or by a power cycle.
+    /// Call this method afterwards so that later changes do not overwrite the
+    /// current register contents with stale values.
+    pub fn refresh(&mut self) -> Result<(), Error<E>> {
+        for &register in CACHED_REGISTERS.iter() {
+            let value = self.read_register(register)?;
+            self.update_cached_register(register, value);
+        }
+        Ok(())
     }
 
     /// Turn power on.
@@ -111,9 +448,48 @@ where
         enable &= !Enable::ALL;
         enable |= mode;
         self.write_register(Register::ENABLE, enable)?;
+        self.enable = Enable::create(enable);
         Ok(())
     }
 
+    /// Set the enabled functions.
+    ///
+    /// All the functions not included in `mode` are disabled.
+    pub fn set_mode_flags(&mut self, mode: Mode) -> Result<(), Error<E>> {
+        let new = Enable::create(mode.bits());
+        self.config_register(&new)?;
+        self.enable = new;
+        Ok(())
+    }
+
+    /// Read the enabled functions.
+    pub fn get_mode_flags(&mut self) -> Result<Mode, Error<E>> {
+        self.read_register(Register::ENABLE).map(Mode::from)
+    }
+
+    /// Read and decode the ENABLE register.
+    ///
+    /// Useful for diagnostics, e.g. to verify which engines are running.
+    pub fn get_enable_flags(&mut self) -> Result<EnableFlags, Error<E>> {
+        self.read_register(Register::ENABLE).map(EnableFlags::from)
+    }
+
+    /// Read whether the device is powered on (PON).
+    pub fn is_powered(&mut self) -> Result<bool, Error<E>> {
+        let enable = self.read_register(Register::ENABLE)?;
+        Ok(Enable::create(enable).is(Enable::PON, true))
+    }
+
+    /// Read whether an engine is enabled.
+    ///
+    /// This only reads the enable bit of the engine. The engine does not run
+    /// unless the device is also powered on, see
+    /// [`is_powered()`](struct.Apds9960.html#method.is_powered).
+    pub fn is_engine_enabled(&mut self, engine: Engine) -> Result<bool, Error<E>> {
+        let enable = self.read_register(Register::ENABLE)?;
+        Ok(Enable::create(enable).is(engine.mask(), true))
+    }
+
     /// Read the current enable register (mode bits).
     pub fn get_mode(&mut self) -> Result<u8, Error<E>> {
         self.read_register(Register::ENABLE)
@@ -154,6 +530,25 @@ where
         self.set_flag_config1(Config1::WLONG, false)
     }
 
+    /// Enable sleep after interrupt.
+    ///
+    /// The device will enter sleep mode after an interrupt is asserted and
+    /// stay there until the interrupt is cleared.
+    pub fn enable_sleep_after_interrupt(&mut self) -> Result<(), Error<E>> {
+        self.set_flag_config3(Config3::SAI, true)
+    }
+
+    /// Disable sleep after interrupt.
+    pub fn disable_sleep_after_interrupt(&mut self) -> Result<(), Error<E>> {
+        self.set_flag_config3(Config3::SAI, false)
+    }
+
+    /// Read whether sleep after interrupt is enabled.
+    pub fn is_sleep_after_interrupt_enabled(&mut self) -> Result<bool, Error<E>> {
+        let config3 = self.read_register(Register::CONFIG3)?;
+        Ok(Config3::create(config3).is(Config3::SAI, true))
+    }
+
     /// Set the waiting time between proximity and / or color and ambient light cycles.
     ///
     /// The value parameter must be a 2's complement of the number of cycles.
@@ -169,16 +564,133 @@ where
         self.write_register(Register::WTIME, value)
     }
 
+    /// Read the waiting time between proximity and / or color and ambient light cycles
+    /// in milliseconds.
+    ///
+    /// This takes long wait into account. Whether waiting is enabled at all is
+    /// not considered.
+    pub fn get_wait_time_ms(&mut self) -> Result<f32, Error<E>> {
+        let wtime = self.read_register(Register::WTIME)?;
+        let mut ms = f32::from(wtime_to_cycles(wtime)) * 2.78;
+        if self.get_wait_long()? {
+            ms *= 12.0;
+        }
+        Ok(ms)
+    }
+
+    /// Read whether long wait is enabled (WLONG).
+    pub fn get_wait_long(&mut self) -> Result<bool, Error<E>> {
+        let config1 = self.read_register(Register::CONFIG1)?;
+        Ok(Config1::create(config1).is(Config1::WLONG, true))
+    }
+
+    /// Set how the data reading methods behave when the data is not ready yet.
+    ///
+    /// Per default this is `ReadMode::NonBlocking`.
+    pub fn set_read_mode(&mut self, mode: ReadMode) {
+        self.read_mode = mode;
+    }
+
+    /// Read the current data reading mode.
+    pub fn read_mode(&self) -> ReadMode {
+        self.read_mode
+    }
+
     /// Force an interrupt.
     pub fn force_interrupt(&mut self) -> Result<(), Error<E>> {
         self.touch_register(Register::IFORCE)
     }
 
     /// Clear all *non-gesture* interrupts.
+    ///
+    /// This clears the proximity and the color / ambient light interrupts (AICLEAR).
+    /// See also [`clear_proximity_interrupt()`](struct.Apds9960.html#method.clear_proximity_interrupt)
+    /// and [`clear_color_interrupt()`](struct.Apds9960.html#method.clear_color_interrupt).
     pub fn clear_interrupts(&mut self) -> Result<(), Error<E>> {
         self.touch_register(Register::AICLEAR)
     }
 
+    /// Set the ambient light and proximity interrupt persistence at once.
+    ///
+    /// Both fields of the PERS register are written in a single
+    /// read-modify-write operation.
+    ///
+    /// * `als`: Number of consecutive ambient light cycles (0-15)
+    /// * `proximity`: Number of consecutive proximity cycles (0-15)
+    ///
+    /// Returns `Error::InvalidParameter` if any of the values is greater than 15.
+    pub fn set_interrupt_persistence(&mut self, als: u8, proximity: u8) -> Result<(), Error<E>> {
+        if als > 0x0F || proximity > 0x0F {
+            return Err(Error::InvalidParameter);
+        }
+        let pers = self.read_register(Register::PERS)?;
+        let pers = (pers & !(Pers::APERS_MASK | Pers::PPERS_MASK))
+            | als
+            | (proximity << Pers::PPERS_SHIFT);
+        self.write_register(Register::PERS, pers)?;
+        self.pers = Pers::create(pers);
+        Ok(())
+    }
+
+    /// Get the ambient light and proximity interrupt persistence.
+    ///
+    /// Returns `(als, proximity)`.
+    pub fn get_interrupt_persistence(&mut self) -> Result<(u8, u8), Error<E>> {
+        let pers = self.read_register(Register::PERS)?;
+        Ok((
+            pers & Pers::APERS_MASK,
+            (pers & Pers::PPERS_MASK) >> Pers::PPERS_SHIFT,
+        ))
+    }
+
+    /// Write a block of consecutive registers in a single I2C transaction.
+    ///
+    /// `data[0]` is written to `start`, `data[1]` to `start + 1` and so on, relying on
+    /// the register address auto-increment of the device. This is useful to configure
+    /// several registers efficiently, e.g. the thresholds at `0x84`-`0x8B` or the
+    /// gesture configuration at `0xA0`-`0xAB`.
+    ///
+    /// The address auto-increments through the whole `0x80`-`0xFF` range, but care is
+    /// needed around read-only registers (e.g. `0x92`-`0x9C`, `0xAE`-`0xAF`) and the
+    /// special function registers `0xE4`-`0xE7`, which act on the address write alone.
+    /// Writing to reserved addresses should be avoided.
+    ///
+    /// The register values cached by the driver are updated if the block covers them.
+    ///
+    /// Returns `Error::InvalidInputData` if `data` is empty or if the block does not lie
+    /// within `0x80`-`0xFF`. In this case nothing is written.
+    pub fn write_registers(&mut self, start: u8, data: &[u8]) -> Result<(), Error<E>> {
+        if !is_valid_register_block(start, data.len()) {
+            return Err(Error::InvalidInputData);
+        }
+        let mut buffer = [0; REGISTER_BLOCK_MAX_LEN + 1];
+        buffer[0] = start;
+        buffer[1..=data.len()].copy_from_slice(data);
+        log_debug!("write registers 0x{:02X}: {:02X?}", start, data);
+        self.i2c
+            .write(DEV_ADDR, &buffer[..=data.len()])
+            .map_err(Error::I2C)?;
+        for (offset, &value) in data.iter().enumerate() {
+            self.update_cached_register(start + offset as u8, value);
+        }
+        Ok(())
+    }
+
+    fn update_cached_register(&mut self, register: u8, value: u8) {
+        match register {
+            Register::ENABLE => self.enable = Enable::create(value),
+            Register::PERS => self.pers = Pers::create(value),
+            Register::CONFIG1 => self.config1 = Config1::create(value),
+            Register::CONFIG2 => self.config2 = Config2::create(value),
+            Register::CONFIG3 => self.config3 = Config3::create(value),
+            Register::GCONF1 => self.gconfig1 = GConfig1::create(value),
+            Register::GCONFIG4 => {
+                self.gconfig4 = GConfig4::create(value).with(GConfig4::GFIFO_CLR, false)
+            }
+            _ => (),
+        }
+    }
+
     impl_set_flag_reg!(set_flag_enable, enable);
     impl_set_flag_reg!(set_flag_config1, config1);
     impl_set_flag_reg!(set_flag_config2, config2);
@@ -189,10 +701,18 @@ where
         self.write_register(T::ADDRESS, reg.value())
     }
 
+    /// Write a single register.
+    ///
+    /// If the register is one of the cached registers, the cached value is updated
+    /// as well so that it cannot drift from the device, even for methods that
+    /// modify the register with a plain read-modify-write.
     pub(crate) fn write_register(&mut self, address: u8, value: u8) -> Result<(), Error<E>> {
+        log_debug!("write register 0x{:02X}: 0x{:02X}", address, value);
         self.i2c
             .write(DEV_ADDR, &[address, value])
-            .map_err(Error::I2C)
+            .map_err(Error::I2C)?;
+        self.update_cached_register(address, value);
+        Ok(())
     }
 
     pub(crate) fn write_double_register(
@@ -200,12 +720,49 @@ where
         start_register: u8,
         value: u16,
     ) -> Result<(), Error<E>> {
+        log_debug!("write register 0x{:02X}: 0x{:04X}", start_register, value);
         self.i2c
             .write(DEV_ADDR, &[start_register, value as u8, (value >> 8) as u8])
             .map_err(Error::I2C)
     }
 
+    fn verify_register(&mut self, register: u8, expected: u8) -> Result<(), Error<E>> {
+        let actual = self.read_register(register)?;
+        if actual != expected {
+            return Err(Error::VerifyFailed {
+                register,
+                expected,
+                actual,
+            });
+        }
+        Ok(())
+    }
+
     pub(crate) fn touch_register(&mut self, address: u8) -> Result<(), Error<E>> {
+        log_debug!("write register 0x{:02X}", address);
         self.i2c.write(DEV_ADDR, &[address]).map_err(Error::I2C)
     }
 }
+
+/// Convert a number of wait cycles into the corresponding WTIME register value.
+///
+/// One cycle is 2.78ms (33.4ms with long wait enabled). The number of cycles is clamped
+/// to the supported range of 1 to 256, so 1 cycle gives `0xFF` and 256 cycles give `0`.
+pub fn wtime_from_cycles(cycles: u16) -> u8 {
+    (256 - cycles.clamp(1, 256)) as u8
+}
+
+/// Convert a WTIME register value into the corresponding number of wait cycles.
+///
+/// This is the inverse of [`wtime_from_cycles()`]: `0xFF` gives 1 cycle and `0` gives 256.
+pub fn wtime_to_cycles(wtime: u8) -> u16 {
+    256 - u16::from(wtime)
+}
+
+/// Maximum length of a register block accessed with a single burst (`0x80`-`0xFF`).
+pub(crate) const REGISTER_BLOCK_MAX_LEN: usize = 0x80;
+
+/// Whether a non-empty block of `len` registers starting at `start` lies within `0x80`-`0xFF`.
+pub(crate) fn is_valid_register_block(start: u8, len: usize) -> bool {
+    start >= Register::ENABLE && len > 0 && usize::from(start) + len <= 0x100
+}
@@ -0,0 +1,48 @@
+//! Register values written by [`init()`](../struct.Apds9960.html#method.init).
+//!
+//! These can be used as a baseline when writing a custom initialization routine.
+
+/// Proximity pulse count and length (PPULSE): 16us, 8 pulses.
+pub const PPULSE: u8 = 0x87;
+/// Proximity up/right photodiode offset (POFFSET_UR).
+pub const POFFSET_UR: u8 = 0;
+/// Proximity down/left photodiode offset (POFFSET_DL).
+pub const POFFSET_DL: u8 = 0;
+/// Proximity interrupt low threshold (PILT).
+pub const PILT: u8 = 0;
+/// Proximity interrupt high threshold (PIHT).
+pub const PIHT: u8 = 50;
+/// Color / ambient light integration time (ATIME): 256 cycles, 712ms.
+pub const ATIME: u8 = 0;
+/// Clear channel ambient light interrupt low threshold (AILTL/AILTH).
+pub const AILT: u16 = 0xFFFF;
+/// Clear channel ambient light interrupt high threshold (AIHTL/AIHTH).
+pub const AIHT: u16 = 0;
+/// Interrupt persistence (PERS): 4 proximity cycles, every ambient light cycle.
+pub const PERS: u8 = 0x40;
+/// Wait time (WTIME): 10 cycles, 27.8ms.
+pub const WTIME: u8 = 246;
+/// Configuration register 1 (CONFIG1): no 12x wait (WLONG).
+pub const CONFIG1: u8 = 0x60;
+/// Configuration register 2 (CONFIG2): LED boost 100%, no saturation interrupts.
+pub const CONFIG2: u8 = 0x01;
+/// Configuration register 3 (CONFIG3): all proximity photodiodes enabled.
+pub const CONFIG3: u8 = 0;
+/// Gesture proximity entry threshold (GPENTH).
+pub const GPENTH: u8 = 40;
+/// Gesture exit threshold (GEXTH).
+pub const GEXTH: u8 = 30;
+/// Gesture configuration register 1 (GCONF1): interrupt after 4 datasets, exit after 1.
+pub const GCONF1: u8 = 0x40;
+/// Gesture configuration register 2 (GCONF2): 4x gain, 100mA LED drive, 2.8ms wait.
+pub const GCONF2: u8 = 0x41;
+/// Gesture pulse count and length (GPULSE): 32us, 10 pulses.
+pub const GPULSE: u8 = 0xC9;
+/// Gesture configuration register 3 (GCONF3): all gesture photodiodes active.
+pub const GCONF3: u8 = 0;
+/// Gesture configuration register 4 (GCONFIG4): gesture mode and interrupts disabled.
+pub const GCONFIG4: u8 = 0;
+/// Gesture up, down, left and right offsets (GOFFSET_U/D/L/R).
+pub const GOFFSET: u8 = 0;
+/// Control register (CONTROL): 100mA LED drive, 4x proximity gain, 4x ambient light gain.
+pub const CONTROL: u8 = 0x09;
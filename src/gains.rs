@@ -3,6 +3,7 @@
 //! This module provides type-safe enums for controlling sensor gains and LED settings,
 //! along with methods to configure them on the APDS9960 sensor.
 
+use core::convert::TryFrom;
 use hal::i2c;
 use {Apds9960, Error, Register};
 
@@ -71,6 +72,59 @@ pub enum LedBoost {
     Percent300 = 3,
 }
 
+macro_rules! impl_two_bit_conversions {
+    ($type:ident, $v0:ident, $v1:ident, $v2:ident, $v3:ident) => {
+        impl TryFrom<u8> for $type {
+            /// The rejected value.
+            type Error = u8;
+
+            /// Convert the 2-bit register field value (0–3) into the variant.
+            fn try_from(value: u8) -> Result<Self, Self::Error> {
+                match value {
+                    0 => Ok($type::$v0),
+                    1 => Ok($type::$v1),
+                    2 => Ok($type::$v2),
+                    3 => Ok($type::$v3),
+                    _ => Err(value),
+                }
+            }
+        }
+
+        impl From<$type> for u8 {
+            fn from(value: $type) -> Self {
+                value as u8
+            }
+        }
+    };
+}
+
+impl_two_bit_conversions!(ProximityGain, X1, X2, X4, X8);
+impl_two_bit_conversions!(LightGain, X1, X4, X16, X64);
+impl_two_bit_conversions!(GestureGain, X1, X2, X4, X8);
+impl_two_bit_conversions!(LedDrive, Ma100, Ma50, Ma25, Ma12_5);
+impl_two_bit_conversions!(LedBoost, Percent100, Percent150, Percent200, Percent300);
+
+/// Gesture wait time between gesture detection cycles
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GestureWaitTime {
+    /// 0 ms
+    Ms0 = 0,
+    /// 2.8 ms
+    Ms2_8 = 1,
+    /// 5.6 ms
+    Ms5_6 = 2,
+    /// 8.4 ms
+    Ms8_4 = 3,
+    /// 14.0 ms
+    Ms14_0 = 4,
+    /// 22.4 ms
+    Ms22_4 = 5,
+    /// 30.8 ms
+    Ms30_8 = 6,
+    /// 39.2 ms
+    Ms39_2 = 7,
+}
+
 impl<I2C, E> Apds9960<I2C>
 where
     I2C: i2c::I2c<Error = E>,
@@ -89,6 +143,13 @@ where
         self.write_register(Register::CONTROL, control)
     }
 
+    /// Set color sensor gain (CONTROL bits 1:0).
+    ///
+    /// Same as [`set_light_gain()`](#method.set_light_gain).
+    pub fn set_color_gain(&mut self, gain: LightGain) -> Result<(), Error<E>> {
+        self.set_light_gain(gain)
+    }
+
     /// Set gesture sensor gain
     pub fn set_gesture_gain(&mut self, gain: GestureGain) -> Result<(), Error<E>> {
         let mut gconf2 = self.read_register(Register::GCONF2)?;
@@ -108,6 +169,16 @@ where
         })
     }
 
+    /// Get gesture sensor gain as a multiplier (1, 2, 4 or 8).
+    pub fn get_gesture_gain_multiplier(&mut self) -> Result<u8, Error<E>> {
+        Ok(match self.get_gesture_gain()? {
+            GestureGain::X1 => 1,
+            GestureGain::X2 => 2,
+            GestureGain::X4 => 4,
+            GestureGain::X8 => 8,
+        })
+    }
+
     /// Set LED drive current for proximity and ALS
     pub fn set_led_drive(&mut self, drive: LedDrive) -> Result<(), Error<E>> {
         let mut control = self.read_register(Register::CONTROL)?;
@@ -145,20 +216,42 @@ where
     /// 
     /// * `pulses`: Number of pulses (0-63, actual pulses = value + 1)
     /// * `length`: Pulse length (0=4us, 1=8us, 2=16us, 3=32us)
+    ///
+    /// Returns `Error::InvalidParameter` if any of the values is out of range.
     pub fn set_proximity_pulse(&mut self, pulses: u8, length: u8) -> Result<(), Error<E>> {
-        let value = ((length & 0x03) << 6) | (pulses & 0x3F);
+        let value = pulse_register_value(pulses, length)?;
         self.write_register(Register::PPULSE, value)
     }
 
+    /// Get proximity pulse count and length
+    ///
+    /// Returns `(pulses, length)` with the same encoding as
+    /// [`set_proximity_pulse()`](struct.Apds9960.html#method.set_proximity_pulse).
+    pub fn get_proximity_pulse(&mut self) -> Result<(u8, u8), Error<E>> {
+        let ppulse = self.read_register(Register::PPULSE)?;
+        Ok((ppulse & 0x3F, ppulse >> 6))
+    }
+
     /// Set gesture pulse count and length
     /// 
     /// * `pulses`: Number of pulses (0-63, actual pulses = value + 1)
     /// * `length`: Pulse length (0=4us, 1=8us, 2=16us, 3=32us)
+    ///
+    /// Returns `Error::InvalidParameter` if any of the values is out of range.
     pub fn set_gesture_pulse(&mut self, pulses: u8, length: u8) -> Result<(), Error<E>> {
-        let value = ((length & 0x03) << 6) | (pulses & 0x3F);
+        let value = pulse_register_value(pulses, length)?;
         self.write_register(Register::GPULSE, value)
     }
 
+    /// Get gesture pulse count and length
+    ///
+    /// Returns `(pulses, length)` with the same encoding as
+    /// [`set_gesture_pulse()`](struct.Apds9960.html#method.set_gesture_pulse).
+    pub fn get_gesture_pulse(&mut self) -> Result<(u8, u8), Error<E>> {
+        let gpulse = self.read_register(Register::GPULSE)?;
+        Ok((gpulse & 0x3F, gpulse >> 6))
+    }
+
     /// Set gesture wait time between gesture detection cycles
     ///
     /// * 0 = 0 ms
@@ -169,9 +262,28 @@ where
     /// * 5 = 22.4 ms
     /// * 6 = 30.8 ms
     /// * 7 = 39.2 ms
+    ///
+    /// Returns `Error::InvalidParameter` for values greater than 7.
     pub fn set_gesture_wait_time(&mut self, time: u8) -> Result<(), Error<E>> {
+        if time > 7 {
+            return Err(Error::InvalidParameter);
+        }
         let mut gconf2 = self.read_register(Register::GCONF2)?;
-        gconf2 = (gconf2 & 0xF8) | (time & 0x07);
+        gconf2 = (gconf2 & 0xF8) | time;
+        self.write_register(Register::GCONF2, gconf2)
+    }
+
+    /// Set gesture gain, gesture LED drive current and gesture wait time at once.
+    ///
+    /// These share the GCONF2 register, which is written in a single operation
+    /// without reading it first.
+    pub fn set_gesture_control(
+        &mut self,
+        gain: GestureGain,
+        drive: LedDrive,
+        wait: GestureWaitTime,
+    ) -> Result<(), Error<E>> {
+        let gconf2 = ((gain as u8) << 5) | ((drive as u8) << 3) | (wait as u8);
         self.write_register(Register::GCONF2, gconf2)
     }
 
@@ -205,6 +317,13 @@ where
         })
     }
 
+    /// Get color sensor gain (CONTROL bits 1:0).
+    ///
+    /// Same as [`get_light_gain()`](#method.get_light_gain).
+    pub fn get_color_gain(&mut self) -> Result<LightGain, Error<E>> {
+        self.get_light_gain()
+    }
+
     /// Get LED drive current for proximity and ALS
     pub fn get_led_drive(&mut self) -> Result<LedDrive, Error<E>> {
         let control = self.read_register(Register::CONTROL)?;
@@ -228,4 +347,13 @@ where
             _ => LedBoost::Percent300,
         })
     }
-}
\ No newline at end of file
+}
+
+/// PPULSE / GPULSE register value. The pulse count is limited to 6 bits and
+/// the pulse length to 2 bits.
+fn pulse_register_value<E>(pulses: u8, length: u8) -> Result<u8, Error<E>> {
+    if pulses > 0x3F || length > 0x03 {
+        return Err(Error::InvalidParameter);
+    }
+    Ok((length << 6) | pulses)
+}
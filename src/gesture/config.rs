@@ -1,9 +1,73 @@
 use hal::i2c;
 use {
+    defaults,
+    proximity::{decode_offset, encode_offset},
     register::{Enable, GConfig1, GConfig4},
-    Apds9960, BitFlags, Error, GestureDataThreshold, Register, DEV_ADDR,
+    Apds9960, BitFlags, Error, GestureDataThreshold, GestureGain, GestureWaitTime, LedBoost,
+    LedDrive, Register, DEV_ADDR,
 };
 
+/// Gesture engine settings applied together with
+/// [`apply_gesture_config()`](struct.Apds9960.html#method.apply_gesture_config).
+///
+/// The default values are the ones written by `init()`. Individual settings can be
+/// changed with the struct update syntax, e.g.
+/// `GestureConfig { entry_threshold: 20, ..GestureConfig::high_sensitivity() }`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GestureConfig {
+    /// Gesture gain. (default: 4x)
+    pub gain: GestureGain,
+    /// Gesture LED drive current. (default: 100mA)
+    pub led_drive: LedDrive,
+    /// LED boost. This is shared with the proximity engine. (default: 100%)
+    pub led_boost: LedBoost,
+    /// Gesture pulse count (0-63, actual pulses = value + 1). (default: 9)
+    pub pulse_count: u8,
+    /// Gesture pulse length (0=4us, 1=8us, 2=16us, 3=32us). (default: 3)
+    pub pulse_length: u8,
+    /// Gesture wait time between gesture detection cycles. (default: 2.8ms)
+    pub wait_time: GestureWaitTime,
+    /// Gesture proximity entry threshold. (default: 40)
+    pub entry_threshold: u8,
+    /// Gesture exit threshold. (default: 30)
+    pub exit_threshold: u8,
+}
+
+impl Default for GestureConfig {
+    fn default() -> Self {
+        GestureConfig {
+            gain: GestureGain::X4,
+            led_drive: LedDrive::Ma100,
+            led_boost: LedBoost::Percent100,
+            pulse_count: 9,
+            pulse_length: 3,
+            wait_time: GestureWaitTime::Ms2_8,
+            entry_threshold: defaults::GPENTH,
+            exit_threshold: defaults::GEXTH,
+        }
+    }
+}
+
+impl GestureConfig {
+    /// Settings for detecting gestures at larger distances.
+    ///
+    /// Compared to the [`default()`](#method.default), this uses the maximum gain,
+    /// 300% LED boost, 16 pulses and lower entry/exit thresholds. This increases
+    /// the power consumption and the sensitivity to ambient noise.
+    pub fn high_sensitivity() -> Self {
+        GestureConfig {
+            gain: GestureGain::X8,
+            led_drive: LedDrive::Ma100,
+            led_boost: LedBoost::Percent300,
+            pulse_count: 15,
+            pulse_length: 3,
+            wait_time: GestureWaitTime::Ms2_8,
+            entry_threshold: 10,
+            exit_threshold: 5,
+        }
+    }
+}
+
 /// Gesture engine configuration.
 impl<I2C, E> Apds9960<I2C>
 where
@@ -19,12 +83,39 @@ where
         self.set_flag_enable(Enable::GEN, false)
     }
 
+    /// Enable gesture sensor with interrupts
+    ///
+    /// Gesture detection relies on the proximity engine, so proximity detection
+    /// is enabled as well.
+    pub fn enable_gesture_sensor(&mut self, interrupts: bool) -> Result<(), Error<E>> {
+        self.write_register(Register::GCONF2, defaults::GCONF2)?;
+        self.write_register(Register::GPULSE, defaults::GPULSE)?;
+        self.set_gesture_proximity_entry_threshold(defaults::GPENTH)?;
+        self.set_gesture_proximity_exit_threshold(defaults::GEXTH)?;
+        if interrupts {
+            self.enable_gesture_interrupts()?;
+        } else {
+            self.disable_gesture_interrupts()?;
+        }
+        self.enable()?;
+        self.enable_proximity()?;
+        self.enable_gesture()
+    }
+
+    /// Disable gesture sensor
+    ///
+    /// Proximity detection is left enabled.
+    pub fn disable_gesture_sensor(&mut self) -> Result<(), Error<E>> {
+        self.disable_gesture_interrupts()?;
+        self.disable_gesture()
+    }
+
     /// Enable gesture mode.
     ///
     /// This can be automatically enabled (depending on proximity thresholds)
     /// and disabled (see GMODE on datasheet).
     pub fn enable_gesture_mode(&mut self) -> Result<(), Error<E>> {
-        self.set_flag_gconfig4(GConfig4::GMODE, true)
+        self.set_gesture_mode(true)
     }
 
     /// Disable gesture mode.
@@ -32,7 +123,23 @@ where
     /// This can be automatically enabled (depending on proximity thresholds)
     /// and disabled (see GMODE on datasheet).
     pub fn disable_gesture_mode(&mut self) -> Result<(), Error<E>> {
-        self.set_flag_gconfig4(GConfig4::GMODE, false)
+        self.set_gesture_mode(false)
+    }
+
+    /// Set the gesture mode (GMODE bit in GCONFIG4).
+    pub fn set_gesture_mode(&mut self, enabled: bool) -> Result<(), Error<E>> {
+        self.set_flag_gconfig4(GConfig4::GMODE, enabled)
+    }
+
+    /// Read whether the device is currently in gesture mode (GMODE bit in GCONFIG4).
+    ///
+    /// The device clears GMODE on its own when the gesture exit conditions are met,
+    /// so the cached value is updated with the value read.
+    pub fn get_gesture_mode(&mut self) -> Result<bool, Error<E>> {
+        let gconf4 = GConfig4::create(self.read_register(Register::GCONFIG4)?);
+        let enabled = gconf4.is(GConfig4::GMODE, true);
+        self.gconfig4 = self.gconfig4.with(GConfig4::GMODE, enabled);
+        Ok(enabled)
     }
 
     /// Enable gesture interrupt generation
@@ -67,6 +174,46 @@ where
         Ok(())
     }
 
+    /// Set the number of datasets in the gesture FIFO after which a gesture
+    /// interrupt is generated (GFIFOTH bits in GCONF1).
+    ///
+    /// This is the same as
+    /// [`set_gesture_data_level_threshold()`](struct.Apds9960.html#method.set_gesture_data_level_threshold).
+    pub fn set_gesture_fifo_interrupt_level(
+        &mut self,
+        threshold: GestureDataThreshold,
+    ) -> Result<(), Error<E>> {
+        self.set_gesture_data_level_threshold(threshold)
+    }
+
+    /// Read the number of datasets in the gesture FIFO after which a gesture
+    /// interrupt is generated (GFIFOTH bits in GCONF1).
+    pub fn get_gesture_fifo_interrupt_level(&mut self) -> Result<GestureDataThreshold, Error<E>> {
+        let gconf1 = GConfig1::create(self.read_register(Register::GCONF1)?);
+        let flags = (
+            gconf1.is(GConfig1::GFIFOTH1, true),
+            gconf1.is(GConfig1::GFIFOTH0, true),
+        );
+        Ok(match flags {
+            (false, false) => GestureDataThreshold::Th1,
+            (false, true) => GestureDataThreshold::Th4,
+            (true, false) => GestureDataThreshold::Th8,
+            (true, true) => GestureDataThreshold::Th16,
+        })
+    }
+
+    /// Apply the gesture engine settings.
+    ///
+    /// Returns `Error::InvalidParameter` if the pulse count or length is out of range.
+    /// In this case nothing is written.
+    pub fn apply_gesture_config(&mut self, config: &GestureConfig) -> Result<(), Error<E>> {
+        self.set_gesture_pulse(config.pulse_count, config.pulse_length)?;
+        self.set_gesture_control(config.gain, config.led_drive, config.wait_time)?;
+        self.set_led_boost(config.led_boost)?;
+        self.set_gesture_proximity_entry_threshold(config.entry_threshold)?;
+        self.set_gesture_proximity_exit_threshold(config.exit_threshold)
+    }
+
     /// Set the gesture proximity entry threshold.
     pub fn set_gesture_proximity_entry_threshold(&mut self, threshold: u8) -> Result<(), Error<E>> {
         self.write_register(Register::GPENTH, threshold)
@@ -77,24 +224,39 @@ where
         self.write_register(Register::GEXTH, threshold)
     }
 
+    /// Set the gesture proximity entry and exit thresholds.
+    ///
+    /// Both registers are written in a single operation.
+    /// Returns `Error::InvalidInputData` if `exit` is greater than `entry`.
+    pub fn set_gesture_proximity_thresholds(
+        &mut self,
+        entry: u8,
+        exit: u8,
+    ) -> Result<(), Error<E>> {
+        if exit > entry {
+            return Err(Error::InvalidInputData);
+        }
+        self.write_double_register(Register::GPENTH, (u16::from(exit) << 8) | u16::from(entry))
+    }
+
     /// Set the gesture up offset.
     pub fn set_gesture_up_offset(&mut self, offset: i8) -> Result<(), Error<E>> {
-        self.write_register(Register::GOFFSET_U, offset as u8)
+        self.write_register(Register::GOFFSET_U, encode_offset(offset))
     }
 
     /// Set the gesture down offset.
     pub fn set_gesture_down_offset(&mut self, offset: i8) -> Result<(), Error<E>> {
-        self.write_register(Register::GOFFSET_D, offset as u8)
+        self.write_register(Register::GOFFSET_D, encode_offset(offset))
     }
 
     /// Set the gesture left offset.
     pub fn set_gesture_left_offset(&mut self, offset: i8) -> Result<(), Error<E>> {
-        self.write_register(Register::GOFFSET_L, offset as u8)
+        self.write_register(Register::GOFFSET_L, encode_offset(offset))
     }
 
     /// Set the gesture right offset.
     pub fn set_gesture_right_offset(&mut self, offset: i8) -> Result<(), Error<E>> {
-        self.write_register(Register::GOFFSET_R, offset as u8)
+        self.write_register(Register::GOFFSET_R, encode_offset(offset))
     }
 
     /// Set the gesture up, down, left and right offsets.
@@ -113,6 +275,19 @@ where
         self.set_gesture_right_offset(offset_right)
     }
 
+    /// Read the gesture up, down, left and right offsets.
+    ///
+    /// Returns `(up, down, left, right)`.
+    pub fn get_gesture_offsets(&mut self) -> Result<(i8, i8, i8, i8), Error<E>> {
+        // GOFFSET_L and GOFFSET_R are not sequential, so the registers are read individually
+        Ok((
+            decode_offset(self.read_register(Register::GOFFSET_U)?),
+            decode_offset(self.read_register(Register::GOFFSET_D)?),
+            decode_offset(self.read_register(Register::GOFFSET_L)?),
+            decode_offset(self.read_register(Register::GOFFSET_R)?),
+        ))
+    }
+
     /// Enable all gesture photodiodes during gesture mode.
     pub fn enable_all_gesture_photodiodes(&mut self) -> Result<(), Error<E>> {
         self.write_register(Register::GCONF3, 0)
@@ -137,12 +312,37 @@ where
         self.write_register(Register::GCONF3, mask)
     }
 
+    /// Get gesture photodiode dimensions.
+    ///
+    /// Returns `(up_down, left_right)` with the same meaning as in
+    /// [`set_gesture_dimensions()`](struct.Apds9960.html#method.set_gesture_dimensions):
+    /// `true` if the photodiodes are enabled.
+    pub fn get_gesture_dimensions(&mut self) -> Result<(bool, bool), Error<E>> {
+        let mask = self.read_register(Register::GCONF3)?;
+        Ok((mask & 0b0000_0011 == 0, mask & 0b0000_1100 == 0))
+    }
+
     /// Clear gesture FIFO and interrupt.
     ///
     /// This clears the gesture FIFO and gesture interrupt, similar to the Python implementation.
+    /// GMODE and GIEN are left untouched. GFIFO_CLR clears itself so it is not
+    /// kept in the cached GCONFIG4 value.
     pub fn clear_gesture_fifo(&mut self) -> Result<(), Error<E>> {
         self.set_flag_gconfig4(GConfig4::GFIFO_CLR, true)
     }
+
+    /// Clear the gesture interrupt.
+    ///
+    /// There is no dedicated clear register for the gesture interrupt (like
+    /// PICLEAR for proximity). Instead, GINT is deasserted when the FIFO is
+    /// emptied, so this sets GFIFO_CLR in GCONFIG4, which clears GINT, GVALID,
+    /// GFIFO_OV and GFIFO_LVL. The GMODE and GIEN settings are kept as they are.
+    ///
+    /// This is the same as
+    /// [`clear_gesture_fifo()`](struct.Apds9960.html#method.clear_gesture_fifo).
+    pub fn clear_gesture_interrupt(&mut self) -> Result<(), Error<E>> {
+        self.clear_gesture_fifo()
+    }
 }
 
 impl<I2C, E> Apds9960<I2C>
@@ -153,27 +353,56 @@ where
     ///
     /// Number of consecutive gesture end occurrences to exit gesture mode.
     /// * `persistence`: 1, 2, 4, or 7 consecutive occurrences
+    ///
+    /// Returns `Error::InvalidParameter` for any other value.
     pub fn set_gesture_exit_persistence(&mut self, persistence: u8) -> Result<(), Error<E>> {
         let value = match persistence {
             1 => 0b00,
             2 => 0b01,
             4 => 0b10,
             7 => 0b11,
-            _ => 0b01, // default to 2
+            _ => return Err(Error::InvalidParameter),
         };
-        let mut gconf1 = self.read_register(Register::GCONF1)?;
-        gconf1 = (gconf1 & 0x9F) | (value << 5);
-        self.write_register(Register::GCONF1, gconf1)
+        let gconf1 = self.read_register(Register::GCONF1)?;
+        self.write_gconfig1((gconf1 & !GConfig1::GEXPERS_MASK) | value)
+    }
+
+    /// Get gesture exit persistence.
+    ///
+    /// Returns the number of consecutive gesture end occurrences to exit gesture mode:
+    /// 1, 2, 4, or 7.
+    pub fn get_gesture_exit_persistence(&mut self) -> Result<u8, Error<E>> {
+        let gconf1 = self.read_register(Register::GCONF1)?;
+        Ok(match gconf1 & GConfig1::GEXPERS_MASK {
+            0b00 => 1,
+            0b01 => 2,
+            0b10 => 4,
+            _ => 7,
+        })
     }
 
     /// Set gesture exit mask.
     ///
-    /// Determines which photodiode pairs are included in gesture exit comparison.
-    /// * `mask`: Bitmask where bit 0=UD, 1=LR, 2=Both, 3=All
+    /// Determines which photodiodes are excluded from the gesture exit comparison.
+    /// * `mask`: Bitmask where bit 3=Up, 2=Down, 1=Left, 0=Right.
+    ///   Setting a bit to 1 excludes that photodiode.
+    ///
+    /// Returns `Error::InvalidParameter` if `mask` is greater than `0x0F`.
     pub fn set_gesture_exit_mask(&mut self, mask: u8) -> Result<(), Error<E>> {
-        let mut gconf1 = self.read_register(Register::GCONF1)?;
-        gconf1 = (gconf1 & 0xE7) | ((mask & 0x03) << 3);
-        self.write_register(Register::GCONF1, gconf1)
+        if mask > 0x0F {
+            return Err(Error::InvalidParameter);
+        }
+        let gconf1 = self.read_register(Register::GCONF1)?;
+        let value = mask << GConfig1::GEXMSK_SHIFT;
+        self.write_gconfig1((gconf1 & !GConfig1::GEXMSK_MASK) | value)
+    }
+
+    /// Get gesture exit mask.
+    ///
+    /// See [`set_gesture_exit_mask()`](struct.Apds9960.html#method.set_gesture_exit_mask).
+    pub fn get_gesture_exit_mask(&mut self) -> Result<u8, Error<E>> {
+        let gconf1 = self.read_register(Register::GCONF1)?;
+        Ok((gconf1 & GConfig1::GEXMSK_MASK) >> GConfig1::GEXMSK_SHIFT)
     }
 
     /// Read gesture enter threshold.
@@ -185,4 +414,11 @@ where
     pub fn get_gesture_proximity_exit_threshold(&mut self) -> Result<u8, Error<E>> {
         self.read_register(Register::GEXTH)
     }
+
+    fn write_gconfig1(&mut self, value: u8) -> Result<(), Error<E>> {
+        let new = GConfig1::create(value);
+        self.config_register(&new)?;
+        self.gconfig1 = new;
+        Ok(())
+    }
 }
@@ -1,5 +1,6 @@
-use hal::i2c;
-use {Apds9960, Error};
+use core::convert::TryFrom;
+use hal::{delay::DelayNs, i2c};
+use {register::GStatus, Apds9960, BitFlags, Error, Register};
 
 /// Gesture direction codes.
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -16,130 +17,659 @@ pub enum Gesture {
     Right = 4,
 }
 
+impl Gesture {
+    /// Direction of the gesture in degrees, clockwise from `Up`.
+    ///
+    /// Returns `None` for `Gesture::None`.
+    pub fn angle(&self) -> Option<u16> {
+        match self {
+            Gesture::None => None,
+            Gesture::Up => Some(0),
+            Gesture::Right => Some(90),
+            Gesture::Down => Some(180),
+            Gesture::Left => Some(270),
+        }
+    }
+}
+
+/// Rotation offset applied to decoded gestures.
+///
+/// The gesture is rotated clockwise, e.g. with `Deg90` an `Up` swipe is
+/// reported as `Right`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Rotation {
+    /// No rotation (default).
+    #[default]
+    Deg0,
+    /// 90°
+    Deg90,
+    /// 180°
+    Deg180,
+    /// 270°
+    Deg270,
+}
+
+impl Rotation {
+    /// Rotation in degrees.
+    pub fn degrees(self) -> u16 {
+        match self {
+            Rotation::Deg0 => 0,
+            Rotation::Deg90 => 90,
+            Rotation::Deg180 => 180,
+            Rotation::Deg270 => 270,
+        }
+    }
+}
+
+impl TryFrom<u16> for Rotation {
+    /// The rejected value.
+    type Error = u16;
+
+    /// Convert 0, 90, 180 or 270 degrees into the rotation.
+    fn try_from(degrees: u16) -> Result<Self, Self::Error> {
+        match degrees {
+            0 => Ok(Rotation::Deg0),
+            90 => Ok(Rotation::Deg90),
+            180 => Ok(Rotation::Deg180),
+            270 => Ok(Rotation::Deg270),
+            _ => Err(degrees),
+        }
+    }
+}
+
+/// Detailed gesture decoding result.
+///
+/// See [`decode_gesture_detailed()`](struct.Apds9960.html#method.decode_gesture_detailed).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GestureResult {
+    /// Decoded gesture, with the configured rotation applied.
+    pub gesture: Gesture,
+    /// How clearly the movement exceeded the decoding threshold, in percent (0-100).
+    ///
+    /// This is 0 if no gesture was detected.
+    pub confidence: u8,
+    /// Number of datasets used for decoding, after filtering.
+    pub datasets: u8,
+}
+
+/// Tuning parameters of the gesture decoding algorithm.
+///
+/// See [`set_gesture_decode_params()`](struct.Apds9960.html#method.set_gesture_decode_params).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GestureDecodeParams {
+    /// Noise floor. A dataset is only used if all four photodiode values are at
+    /// least this value. (default: 30)
+    pub noise_floor: u8,
+    /// Minimum change of the up/down ratio between the first and the last dataset
+    /// for it to be considered a movement. (default: 30)
+    pub ud_threshold: u8,
+    /// Minimum change of the left/right ratio between the first and the last dataset
+    /// for it to be considered a movement. (default: 30)
+    pub lr_threshold: u8,
+}
+
+impl Default for GestureDecodeParams {
+    fn default() -> Self {
+        GestureDecodeParams {
+            noise_floor: 30,
+            ud_threshold: 30,
+            lr_threshold: 30,
+        }
+    }
+}
+
+/// Gesture decoding algorithm.
+///
+/// Implement this to use a custom algorithm with
+/// [`decode_gesture_with()`](struct.Apds9960.html#method.decode_gesture_with).
+pub trait GestureDecoder {
+    /// Decode a gesture from the gesture FIFO datasets.
+    ///
+    /// Each dataset contains the up, down, left and right photodiode values in
+    /// this order. The datasets are in the order they were read from the FIFO.
+    fn decode(&mut self, datasets: &[[u8; 4]]) -> Gesture;
+}
+
+/// The gesture decoding algorithm used by
+/// [`decode_gesture()`](struct.Apds9960.html#method.decode_gesture).
+///
+/// This compares the photodiode ratios of the first and the last dataset.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct DefaultGestureDecoder {
+    /// Decoding parameters. Only the thresholds are used here since the noise
+    /// floor is applied while reading the FIFO.
+    pub params: GestureDecodeParams,
+}
+
+impl DefaultGestureDecoder {
+    /// Create a new decoder with the given parameters.
+    pub fn new(params: GestureDecodeParams) -> Self {
+        DefaultGestureDecoder { params }
+    }
+}
+
+impl GestureDecoder for DefaultGestureDecoder {
+    fn decode(&mut self, datasets: &[[u8; 4]]) -> Gesture {
+        analyze_datasets(datasets, &self.params).gesture
+    }
+}
+
 impl<I2C, E> Apds9960<I2C>
 where
     I2C: i2c::I2c<Error = E>,
 {
     /// Decode a gesture by reading the FIFO data and applying the same algorithm
     /// used in the Python/C++ drivers (filtering, ratios, deltas, then decision tree).
+    ///
+    /// Returns `nb::Error::WouldBlock` if no gesture data is available.
+    ///
+    /// If the gesture FIFO has overflown (GFOV), its contents are not reliable.
+    /// In this case the FIFO is cleared and `Gesture::None` is returned.
+    ///
+    /// Returns `Error::GestureSaturated` if more than half of the datasets read were
+    /// saturated (all photodiode values at 255), e.g. because the hand was very close
+    /// to the sensor. Reducing the gesture gain or LED drive helps in that case.
     pub fn decode_gesture(&mut self) -> nb::Result<Gesture, Error<E>> {
-        if !self.is_gesture_data_valid().map_err(nb::Error::Other)? {
+        let analysis = self.analyze_gesture()?;
+        Ok(self.rotate_gesture(analysis.gesture))
+    }
+
+    /// Decode a gesture like [`decode_gesture()`](struct.Apds9960.html#method.decode_gesture)
+    /// and clear the gesture FIFO afterwards.
+    ///
+    /// `decode_gesture()` only consumes the datasets available while decoding, so
+    /// data arriving afterwards would be part of the next decoding. This method
+    /// discards any remaining FIFO contents (and the gesture interrupt) so that the
+    /// next call starts with a fresh gesture.
+    ///
+    /// Returns `nb::Error::WouldBlock` if no gesture data is available. The FIFO
+    /// is not cleared in that case.
+    pub fn read_gesture(&mut self) -> nb::Result<Gesture, Error<E>> {
+        let gesture = self.decode_gesture()?;
+        self.clear_gesture_fifo().map_err(nb::Error::Other)?;
+        Ok(gesture)
+    }
+
+    /// Decode a gesture like [`decode_gesture()`](struct.Apds9960.html#method.decode_gesture)
+    /// and additionally estimate how fast the swipe was.
+    ///
+    /// The speed estimate (0-255) is based on the change of the photodiode ratios between
+    /// the first and the last dataset divided by the number of datasets: a large change
+    /// captured in few datasets means a fast swipe. This is only a relative measure since
+    /// the time between datasets depends on the gesture wait time and pulse settings.
+    ///
+    /// If no gesture is detected the speed is 0.
+    pub fn decode_gesture_with_velocity(&mut self) -> nb::Result<(Gesture, u8), Error<E>> {
+        let analysis = self.analyze_gesture()?;
+        let speed = if analysis.gesture == Gesture::None {
+            0
+        } else {
+            let span = core::cmp::max(analysis.delta_ud.abs(), analysis.delta_lr.abs()) as u32;
+            let intervals = analysis.dataset_count as u32 - 1;
+            core::cmp::min(span * 8 / intervals, 255) as u8
+        };
+        Ok((self.rotate_gesture(analysis.gesture), speed))
+    }
+
+    /// Decode a gesture like [`decode_gesture()`](struct.Apds9960.html#method.decode_gesture)
+    /// and additionally report how confident the decision is.
+    ///
+    /// The confidence is derived from how far the change of the photodiode ratio along
+    /// the axis of the gesture exceeded its threshold (see
+    /// [`GestureDecodeParams`](struct.GestureDecodeParams.html)), relative to the
+    /// largest possible change: a change just above the threshold gives a confidence
+    /// close to 0 and the largest possible change gives 100. Callers can use this to
+    /// discard marginal gestures.
+    ///
+    /// Returns `nb::Error::WouldBlock` if no gesture data is available.
+    pub fn decode_gesture_detailed(&mut self) -> nb::Result<GestureResult, Error<E>> {
+        let analysis = self.analyze_gesture()?;
+        let params = &self.gesture_decode_params;
+        let confidence = match analysis.gesture {
+            Gesture::Up | Gesture::Down => confidence(analysis.delta_ud, params.ud_threshold),
+            Gesture::Left | Gesture::Right => confidence(analysis.delta_lr, params.lr_threshold),
+            Gesture::None => 0,
+        };
+        Ok(GestureResult {
+            gesture: self.rotate_gesture(analysis.gesture),
+            confidence,
+            datasets: analysis.dataset_count as u8,
+        })
+    }
+
+    /// Decode a gesture like [`decode_gesture()`](struct.Apds9960.html#method.decode_gesture)
+    /// but give up after about `max_ms` milliseconds.
+    ///
+    /// `decode_gesture()` keeps reading the FIFO for as long as the device produces
+    /// valid gesture data, which can take indefinitely e.g. if a hand stays in front
+    /// of the sensor. Here, the delay is used to wait 1ms between FIFO reads and if
+    /// the data is still valid after `max_ms` milliseconds, the FIFO is cleared and
+    /// `Gesture::None` is returned.
+    ///
+    /// Returns `nb::Error::WouldBlock` if no gesture data is available.
+    pub fn decode_gesture_timeout<D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+        max_ms: u32,
+    ) -> nb::Result<Gesture, Error<E>> {
+        let mut elapsed_ms = 0;
+        let analysis = self.analyze_gesture_with(|| {
+            delay.delay_ms(1);
+            elapsed_ms += 1;
+            elapsed_ms <= max_ms
+        })?;
+        match analysis {
+            Some(analysis) => Ok(self.rotate_gesture(analysis.gesture)),
+            None => {
+                self.clear_gesture_fifo().map_err(nb::Error::Other)?;
+                Ok(Gesture::None)
+            }
+        }
+    }
+
+    /// Decode a gesture with a custom algorithm.
+    ///
+    /// The gesture FIFO is read and filtered like in
+    /// [`decode_gesture()`](struct.Apds9960.html#method.decode_gesture) (including
+    /// the noise floor of the [decode parameters](struct.GestureDecodeParams.html))
+    /// and the remaining datasets are handed to `decoder`. The configured rotation
+    /// is applied to its result.
+    ///
+    /// The decoder is not called if no dataset is left after filtering or if the
+    /// FIFO has overflown. `Gesture::None` is returned in that case.
+    ///
+    /// Returns `nb::Error::WouldBlock` if no gesture data is available.
+    pub fn decode_gesture_with<D: GestureDecoder>(
+        &mut self,
+        decoder: &mut D,
+    ) -> nb::Result<Gesture, Error<E>> {
+        let mut datasets = [[0u8; 4]; 32];
+        let dataset_count = self
+            .read_valid_gesture_datasets(&mut datasets, || true)?
+            .unwrap_or(0);
+        if dataset_count == 0 {
+            return Ok(Gesture::None);
+        }
+        let gesture = decoder.decode(&datasets[..dataset_count]);
+        Ok(self.rotate_gesture(gesture))
+    }
+
+    fn analyze_gesture(&mut self) -> nb::Result<GestureAnalysis, Error<E>> {
+        let analysis = self.analyze_gesture_with(|| true)?;
+        Ok(analysis.unwrap_or_else(|| analyze_datasets(&[], &self.gesture_decode_params)))
+    }
+
+    /// Returns `None` if `keep_reading` stopped the FIFO reading.
+    fn analyze_gesture_with<F: FnMut() -> bool>(
+        &mut self,
+        keep_reading: F,
+    ) -> nb::Result<Option<GestureAnalysis>, Error<E>> {
+        let mut datasets = [[0u8; 4]; 32];
+        let dataset_count = match self.read_valid_gesture_datasets(&mut datasets, keep_reading)? {
+            Some(count) => count,
+            None => return Ok(None),
+        };
+        Ok(Some(analyze_datasets(
+            &datasets[..dataset_count],
+            &self.gesture_decode_params,
+        )))
+    }
+
+    /// Check the gesture status and drain the gesture FIFO into `datasets`.
+    ///
+    /// Returns `nb::Error::WouldBlock` if no gesture data is available.
+    /// If the FIFO has overflown, it is cleared and no datasets are returned.
+    /// Returns `None` if `keep_reading` stopped the FIFO reading.
+    fn read_valid_gesture_datasets<F: FnMut() -> bool>(
+        &mut self,
+        datasets: &mut [[u8; 4]],
+        keep_reading: F,
+    ) -> nb::Result<Option<usize>, Error<E>> {
+        let status = self
+            .read_register(Register::GSTATUS)
+            .map_err(nb::Error::Other)?;
+        let status = GStatus::create(status);
+        if status.is(GStatus::GFOV, true) {
+            log_warn!("gesture FIFO overflow, clearing FIFO");
+            self.clear_gesture_fifo().map_err(nb::Error::Other)?;
+            return Ok(Some(0));
+        }
+        if !status.is(GStatus::GVALID, true) {
             return Err(nb::Error::WouldBlock);
         }
+        self.read_gesture_datasets(datasets, keep_reading)
+            .map_err(nb::Error::Other)
+    }
 
+    /// Drain the gesture FIFO into `datasets`.
+    ///
+    /// Reading stops once `gesture_max_datasets` usable datasets have been collected.
+    /// The rest of the FIFO is then cleared so that it is not decoded as a separate
+    /// gesture later.
+    ///
+    /// The FIFO level is only read again (after checking GVALID) once the datasets
+    /// known to be available have been read in as few bursts as possible.
+    /// The 128-byte buffer holds the complete 32-dataset FIFO.
+    ///
+    /// Returns `None` if `keep_reading` returned `false` before the FIFO was drained.
+    fn read_gesture_datasets<F: FnMut() -> bool>(
+        &mut self,
+        datasets: &mut [[u8; 4]],
+        mut keep_reading: F,
+    ) -> Result<Option<usize>, Error<E>> {
         let mut buffer = [0u8; 128];
-        let mut datasets = [[0u8; 4]; 32];
         let mut dataset_count = 0;
+        let mut read_count = 0;
+        let mut saturated_count = 0;
+        let floor = self.gesture_decode_params.noise_floor;
+        let max_datasets = core::cmp::min(datasets.len(), usize::from(self.gesture_max_datasets));
+        let mut level = self.read_gesture_data_level()?;
 
-        loop {
-            if !self.is_gesture_data_valid().map_err(nb::Error::Other)? {
-                break;
+        while dataset_count < max_datasets {
+            if !keep_reading() {
+                return Ok(None);
             }
-
-            let level = self.read_gesture_data_level().map_err(nb::Error::Other)?;
             if level == 0 {
-                break;
+                if !self.is_gesture_data_valid()? {
+                    break;
+                }
+                level = self.read_gesture_data_level()?;
+                if level == 0 {
+                    break;
+                }
             }
 
             let byte_count = core::cmp::min(buffer.len(), 4 * level as usize);
-            match self.read_gesture_data(&mut buffer[..byte_count]) {
-                Ok(_) => {}
-                Err(nb::Error::WouldBlock) => break,
-                Err(nb::Error::Other(e)) => return Err(nb::Error::Other(e)),
-            }
+            self.read_data(Register::GFIFO_U, &mut buffer[..byte_count])?;
+            level -= (byte_count / 4) as u8;
 
             for chunk in buffer[..byte_count].chunks_exact(4) {
-                let (u, d, l, r) = (chunk[0], chunk[1], chunk[2], chunk[3]);
-                if u >= 30
-                    && d >= 30
-                    && l >= 30
-                    && r >= 30
-                    && !(u == 0 && d == 0 && l == 0 && r == 0)
-                    && !(u == 255 && d == 255 && l == 255 && r == 255)
-                {
-                    if dataset_count < datasets.len() {
-                        datasets[dataset_count] = [u, d, l, r];
-                        dataset_count += 1;
-                    } else {
-                        break;
-                    }
+                let dataset = [chunk[0], chunk[1], chunk[2], chunk[3]];
+                read_count += 1;
+                if dataset == SATURATED_DATASET {
+                    saturated_count += 1;
+                }
+                // Datasets past the limit are still taken into account above.
+                if is_usable_dataset(dataset, floor) && dataset_count < max_datasets {
+                    datasets[dataset_count] = dataset;
+                    dataset_count += 1;
                 }
             }
         }
+        if dataset_count == max_datasets {
+            self.clear_gesture_fifo()?;
+        }
+        if is_mostly_saturated(saturated_count, read_count) {
+            return Err(Error::GestureSaturated);
+        }
+        Ok(Some(dataset_count))
+    }
 
-        if dataset_count < 2 {
+    /// Decode a gesture incrementally, for callers polling faster than a gesture completes.
+    ///
+    /// Each call reads the datasets currently available in the gesture FIFO (filtered
+    /// like in [`decode_gesture()`](struct.Apds9960.html#method.decode_gesture)) and
+    /// appends them to a buffer kept by the driver, without waiting for more data.
+    /// Once the FIFO has become empty after having had data, the motion is considered
+    /// complete: the accumulated datasets are decoded, the buffer is cleared and the
+    /// gesture is returned. If more than 32 datasets are accumulated, the last one is
+    /// replaced so that the end of the motion is kept.
+    ///
+    /// Returns `nb::Error::WouldBlock` while the motion is in progress or if there is
+    /// no gesture data. If the FIFO has overflown, it is cleared together with the
+    /// buffer and `Gesture::None` is returned. Like `decode_gesture()`, this returns
+    /// `Error::GestureSaturated` if most of the accumulated datasets were saturated.
+    pub fn feed_gesture(&mut self) -> nb::Result<Gesture, Error<E>> {
+        let status = self
+            .read_register(Register::GSTATUS)
+            .map_err(nb::Error::Other)?;
+        let status = GStatus::create(status);
+        if status.is(GStatus::GFOV, true) {
+            log_warn!("gesture FIFO overflow, clearing FIFO");
+            self.gesture_accumulator = GestureAccumulator::default();
+            self.clear_gesture_fifo().map_err(nb::Error::Other)?;
             return Ok(Gesture::None);
         }
+        if status.is(GStatus::GVALID, true) {
+            self.accumulate_gesture_datasets()
+                .map_err(nb::Error::Other)?;
+            return Err(nb::Error::WouldBlock);
+        }
+        if !self.gesture_accumulator.active {
+            return Err(nb::Error::WouldBlock);
+        }
+        let accumulator = core::mem::take(&mut self.gesture_accumulator);
+        if is_mostly_saturated(accumulator.saturated, accumulator.total) {
+            return Err(nb::Error::Other(Error::GestureSaturated));
+        }
+        let analysis = analyze_datasets(
+            &accumulator.datasets[..accumulator.count],
+            &self.gesture_decode_params,
+        );
+        Ok(self.rotate_gesture(analysis.gesture))
+    }
 
-        let first = datasets[0];
-        let last = datasets[dataset_count - 1];
-
-        let f_r_ud =
-            ((first[0] as i32 - first[1] as i32) * 100) / (first[0] as i32 + first[1] as i32);
-        let f_r_lr =
-            ((first[2] as i32 - first[3] as i32) * 100) / (first[2] as i32 + first[3] as i32);
-        let l_r_ud = ((last[0] as i32 - last[1] as i32) * 100) / (last[0] as i32 + last[1] as i32);
-        let l_r_lr = ((last[2] as i32 - last[3] as i32) * 100) / (last[2] as i32 + last[3] as i32);
-
-        let delta_ud = l_r_ud - f_r_ud;
-        let delta_lr = l_r_lr - f_r_lr;
+    fn accumulate_gesture_datasets(&mut self) -> Result<(), Error<E>> {
+        self.gesture_accumulator.active = true;
+        let level = self.read_gesture_data_level()?;
+        if level == 0 {
+            return Ok(());
+        }
+        let mut buffer = [0u8; 128];
+        let byte_count = core::cmp::min(buffer.len(), 4 * level as usize);
+        self.read_data(Register::GFIFO_U, &mut buffer[..byte_count])?;
+        let floor = self.gesture_decode_params.noise_floor;
+        for chunk in buffer[..byte_count].chunks_exact(4) {
+            let dataset = [chunk[0], chunk[1], chunk[2], chunk[3]];
+            self.gesture_accumulator.total += 1;
+            if dataset == SATURATED_DATASET {
+                self.gesture_accumulator.saturated += 1;
+            }
+            if is_usable_dataset(dataset, floor) {
+                self.gesture_accumulator.push(dataset);
+            }
+        }
+        Ok(())
+    }
 
-        let state_ud = if delta_ud >= 30 {
-            1
-        } else if delta_ud <= -30 {
-            -1
-        } else {
-            0
-        };
+    /// Set the parameters used by the gesture decoding algorithm.
+    ///
+    /// This allows tuning the decoding sensitivity to the optical setup.
+    /// The defaults match the Python/C++ drivers.
+    pub fn set_gesture_decode_params(&mut self, params: GestureDecodeParams) {
+        self.gesture_decode_params = params;
+    }
 
-        let state_lr = if delta_lr >= 30 {
-            1
-        } else if delta_lr <= -30 {
-            -1
-        } else {
-            0
-        };
+    /// Read the parameters used by the gesture decoding algorithm.
+    pub fn gesture_decode_params(&self) -> GestureDecodeParams {
+        self.gesture_decode_params
+    }
 
-        let gesture = match (state_ud, state_lr) {
-            (-1, 0) => Gesture::Up,
-            (1, 0) => Gesture::Down,
-            (0, -1) => Gesture::Left,
-            (0, 1) => Gesture::Right,
-            (-1, 1) if delta_ud.abs() > delta_lr.abs() => Gesture::Up,
-            (-1, 1) => Gesture::Right,
-            (1, -1) if delta_ud.abs() > delta_lr.abs() => Gesture::Down,
-            (1, -1) => Gesture::Left,
-            (-1, -1) if delta_ud.abs() > delta_lr.abs() => Gesture::Up,
-            (-1, -1) => Gesture::Left,
-            (1, 1) if delta_ud.abs() > delta_lr.abs() => Gesture::Down,
-            (1, 1) => Gesture::Right,
-            _ => Gesture::None,
-        };
+    /// Set the maximum number of datasets the gesture decoding uses (default: 32).
+    ///
+    /// Once this many usable datasets have been read, the decoding stops reading the
+    /// FIFO and decides on the gesture. The rest of the FIFO is cleared (GFIFO_CLR) so
+    /// that the rest of the motion is not decoded as a separate gesture. A lower value
+    /// bounds the latency for long gestures, e.g. a hand moving slowly, at the cost of
+    /// only considering the beginning of the motion.
+    ///
+    /// The value is limited to the range 2 to 32 since at least two datasets are
+    /// needed to decode a gesture and the FIFO holds 32 datasets.
+    /// This does not apply to [`feed_gesture()`](struct.Apds9960.html#method.feed_gesture).
+    pub fn set_gesture_max_datasets(&mut self, max: u8) {
+        self.gesture_max_datasets = max.clamp(2, 32);
+    }
 
-        Ok(self.rotate_gesture(gesture))
+    /// Read the maximum number of datasets the gesture decoding uses.
+    pub fn gesture_max_datasets(&self) -> u8 {
+        self.gesture_max_datasets
     }
 
-    /// Set the rotation offset that is applied to all decoded gestures.
+    /// Set the rotation offset in degrees that is applied to all decoded gestures.
+    ///
+    /// Only 0, 90, 180 and 270 are valid. Otherwise `Error::InvalidRotation` is returned.
+    /// See also [`set_rotation_enum()`](struct.Apds9960.html#method.set_rotation_enum).
     pub fn set_rotation(&mut self, degrees: u16) -> Result<(), Error<E>> {
-        if ![0, 90, 180, 270].contains(&degrees) {
-            return Err(Error::InvalidRotation);
-        }
-        self.rotation = degrees;
+        let rotation = Rotation::try_from(degrees).map_err(|_| Error::InvalidRotation)?;
+        self.set_rotation_enum(rotation);
         Ok(())
     }
 
-    /// Read the current rotation offset.
+    /// Set the rotation offset that is applied to all decoded gestures.
+    pub fn set_rotation_enum(&mut self, rotation: Rotation) {
+        self.rotation = rotation;
+    }
+
+    /// Read the current rotation offset in degrees.
     pub fn rotation(&self) -> u16 {
-        self.rotation
+        self.rotation.degrees()
     }
 
     fn rotate_gesture(&self, gesture: Gesture) -> Gesture {
-        if self.rotation == 0 {
-            return gesture;
-        }
         let dir_lookup = [Gesture::Up, Gesture::Right, Gesture::Down, Gesture::Left];
-        let idx = dir_lookup.iter().position(|&g| g == gesture).unwrap_or(0);
-        let rotated_idx = (idx + (self.rotation / 90) as usize) % 4;
-        dir_lookup[rotated_idx]
+        match dir_lookup.iter().position(|&g| g == gesture) {
+            Some(idx) => dir_lookup[(idx + self.rotation as usize) % 4],
+            None => gesture,
+        }
+    }
+}
+
+/// Gesture datasets accumulated by `feed_gesture()`.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct GestureAccumulator {
+    datasets: [[u8; 4]; 32],
+    count: usize,
+    /// Whether gesture data has been seen since the last decoding.
+    active: bool,
+    /// Number of datasets read, including the ones filtered out.
+    total: usize,
+    /// Number of saturated datasets read.
+    saturated: usize,
+}
+
+impl GestureAccumulator {
+    fn push(&mut self, dataset: [u8; 4]) {
+        if self.count < self.datasets.len() {
+            self.count += 1;
+        }
+        self.datasets[self.count - 1] = dataset;
+    }
+}
+
+/// Gesture dataset with all photodiodes saturated.
+const SATURATED_DATASET: [u8; 4] = [255; 4];
+
+/// Whether most of the `total` datasets read were saturated.
+fn is_mostly_saturated(saturated: usize, total: usize) -> bool {
+    saturated * 2 > total
+}
+
+/// Whether a gesture dataset can be used for decoding.
+///
+/// All photodiode values must be at least `floor`. Datasets with all values
+/// at 0 or at 255 carry no direction information.
+fn is_usable_dataset(dataset: [u8; 4], floor: u8) -> bool {
+    dataset.iter().all(|&value| value >= floor) && dataset != [0; 4] && dataset != SATURATED_DATASET
+}
+
+/// Intermediate results of the gesture decoding algorithm.
+struct GestureAnalysis {
+    gesture: Gesture,
+    delta_ud: i32,
+    delta_lr: i32,
+    dataset_count: usize,
+}
+
+fn analyze_datasets(datasets: &[[u8; 4]], params: &GestureDecodeParams) -> GestureAnalysis {
+    let dataset_count = datasets.len();
+    if dataset_count < 2 {
+        return GestureAnalysis {
+            gesture: Gesture::None,
+            delta_ud: 0,
+            delta_lr: 0,
+            dataset_count,
+        };
+    }
+
+    let first = datasets[0];
+    let last = datasets[dataset_count - 1];
+
+    let f_r_ud = ratio(first[0], first[1]);
+    let f_r_lr = ratio(first[2], first[3]);
+    let l_r_ud = ratio(last[0], last[1]);
+    let l_r_lr = ratio(last[2], last[3]);
+
+    // The ratios are within [-100, 100], so the deltas are within [-200, 200].
+    let delta_ud = l_r_ud - f_r_ud;
+    let delta_lr = l_r_lr - f_r_lr;
+
+    let ud_threshold = i32::from(params.ud_threshold);
+    let lr_threshold = i32::from(params.lr_threshold);
+
+    let state_ud = if delta_ud >= ud_threshold {
+        1
+    } else if delta_ud <= -ud_threshold {
+        -1
+    } else {
+        0
+    };
+
+    let state_lr = if delta_lr >= lr_threshold {
+        1
+    } else if delta_lr <= -lr_threshold {
+        -1
+    } else {
+        0
+    };
+
+    let gesture = match (state_ud, state_lr) {
+        (-1, 0) => Gesture::Up,
+        (1, 0) => Gesture::Down,
+        (0, -1) => Gesture::Left,
+        (0, 1) => Gesture::Right,
+        (-1, 1) if delta_ud.abs() > delta_lr.abs() => Gesture::Up,
+        (-1, 1) => Gesture::Right,
+        (1, -1) if delta_ud.abs() > delta_lr.abs() => Gesture::Down,
+        (1, -1) => Gesture::Left,
+        (-1, -1) if delta_ud.abs() > delta_lr.abs() => Gesture::Up,
+        (-1, -1) => Gesture::Left,
+        (1, 1) if delta_ud.abs() > delta_lr.abs() => Gesture::Down,
+        (1, 1) => Gesture::Right,
+        _ => Gesture::None,
+    };
+
+    GestureAnalysis {
+        gesture,
+        delta_ud,
+        delta_lr,
+        dataset_count,
+    }
+}
+
+/// Normalized difference of two photodiode values in percent.
+///
+/// The result is within [-100, 100]: the magnitude of the difference is never
+/// greater than the sum. The intermediate values fit easily in an `i32`
+/// (at most 255 * 100). The result is clamped nevertheless so that the
+/// delta computations can rely on this range.
+///
+/// Both values can be zero if the noise floor is set to zero. This is treated
+/// as a ratio of zero.
+fn ratio(a: u8, b: u8) -> i32 {
+    let sum = i32::from(a) + i32::from(b);
+    if sum == 0 {
+        return 0;
+    }
+    (((i32::from(a) - i32::from(b)) * 100) / sum).clamp(-100, 100)
+}
+
+/// How far `delta` exceeds `threshold`, in percent of the remaining range up to
+/// the largest possible delta (200).
+fn confidence(delta: i32, threshold: u8) -> u8 {
+    let range = 200 - i32::from(threshold);
+    if range <= 0 {
+        return 100;
     }
+    let excess = (delta.abs() - i32::from(threshold)).clamp(0, range);
+    (excess * 100 / range) as u8
 }
@@ -1,3 +1,5 @@
 pub mod config;
 pub mod decoder;
 pub mod reading;
+pub mod repeat;
+pub mod smoother;
@@ -1,6 +1,19 @@
 use hal::i2c;
 use {register::GStatus, Apds9960, BitFlags, Error, Register};
 
+/// Gesture FIFO status.
+///
+/// See [`read_gesture_status()`](struct.Apds9960.html#method.read_gesture_status).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct GestureStatus {
+    /// Number of datasets available in the gesture FIFO (GFLVL).
+    pub level: u8,
+    /// Whether valid gesture data is available (GVALID).
+    pub valid: bool,
+    /// Whether the gesture FIFO has overflown (GFOV).
+    pub overflow: bool,
+}
+
 /// Gesture data reading.
 impl<I2C, E> Apds9960<I2C>
 where
@@ -18,6 +31,14 @@ where
         Ok(GStatus::create(status).is(GStatus::GVALID, true))
     }
 
+    /// Read whether a gesture is available for reading (GVALID in GSTATUS).
+    ///
+    /// Same as [`is_gesture_data_valid()`](struct.Apds9960.html#method.is_gesture_data_valid).
+    #[allow(clippy::wrong_self_convention)]
+    pub fn is_gesture_available(&mut self) -> Result<bool, Error<E>> {
+        self.is_gesture_data_valid()
+    }
+
     /// Read whether the gesture data has overflown.
     #[allow(clippy::wrong_self_convention)]
     pub fn has_gesture_data_overflown(&mut self) -> Result<bool, Error<E>> {
@@ -25,6 +46,20 @@ where
         Ok(GStatus::create(status).is(GStatus::GFOV, true))
     }
 
+    /// Read the gesture FIFO level and status at once.
+    ///
+    /// GFLVL and GSTATUS are read in a single burst.
+    pub fn read_gesture_status(&mut self) -> Result<GestureStatus, Error<E>> {
+        let mut data = [0; 2];
+        self.read_data(Register::GFLVL, &mut data)?;
+        let status = GStatus::create(data[1]);
+        Ok(GestureStatus {
+            level: data[0],
+            valid: status.is(GStatus::GVALID, true),
+            overflow: status.is(GStatus::GFOV, true),
+        })
+    }
+
     /// Read gesture data.
     ///
     /// Will read the gesture data up to the minimum of: gesture data level, array size.
@@ -46,4 +81,26 @@ where
             .map_err(nb::Error::Other)?;
         Ok(())
     }
+    /// Read all the datasets currently stored in the gesture FIFO.
+    ///
+    /// Reads the gesture data level and then reads `4 * level` bytes (bounded by
+    /// the number of complete datasets fitting in `out`) from the FIFO in a single
+    /// burst. No validity check or decoding is done, so this is useful for debugging
+    /// or custom algorithms.
+    ///
+    /// The data contents are organized as in
+    /// [`read_gesture_data()`](struct.Apds9960.html#method.read_gesture_data).
+    ///
+    /// Returns the number of bytes written to `out`, which is always a multiple of 4
+    /// so that the FIFO is never left in the middle of a dataset. If the FIFO is
+    /// empty or `out` is shorter than 4 bytes, nothing else is read and `0` is returned.
+    pub fn read_gesture_fifo_all(&mut self, out: &mut [u8]) -> Result<usize, Error<E>> {
+        let level = self.read_gesture_data_level()?;
+        let byte_count = 4 * core::cmp::min(out.len() / 4, level as usize);
+        if byte_count == 0 {
+            return Ok(0);
+        }
+        self.read_data(Register::GFIFO_U, &mut out[..byte_count])?;
+        Ok(byte_count)
+    }
 }
@@ -0,0 +1,60 @@
+use gesture::decoder::Gesture;
+
+/// Detects quickly repeated gestures in the same direction.
+///
+/// This can be used to implement accelerated scrolling when the user keeps swiping
+/// in the same direction. Feed every decoded gesture to
+/// [`update()`](struct.RepeatGestureDetector.html#method.update) together with a
+/// monotonic timestamp in milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RepeatGestureDetector {
+    window_ms: u32,
+    last: Gesture,
+    last_ms: u32,
+    count: u8,
+}
+
+impl RepeatGestureDetector {
+    /// Create a new detector.
+    ///
+    /// A gesture is considered a repetition if it happens in the same direction
+    /// as the previous one and no more than `window_ms` milliseconds after it.
+    pub fn new(window_ms: u32) -> Self {
+        RepeatGestureDetector {
+            window_ms,
+            last: Gesture::None,
+            last_ms: 0,
+            count: 0,
+        }
+    }
+
+    /// Process a new gesture detected at `now_ms`.
+    ///
+    /// Returns the gesture together with the number of times it has been repeated
+    /// within the window. The first gesture of a series has a repeat count of 0.
+    /// The count saturates at 255.
+    ///
+    /// `Gesture::None` is returned as-is with a count of 0 and does not change
+    /// the state of the detector, so this can be called on every poll.
+    ///
+    /// The timestamp may wrap around.
+    pub fn update(&mut self, gesture: Gesture, now_ms: u32) -> (Gesture, u8) {
+        if gesture == Gesture::None {
+            return (Gesture::None, 0);
+        }
+        if gesture == self.last && now_ms.wrapping_sub(self.last_ms) <= self.window_ms {
+            self.count = self.count.saturating_add(1);
+        } else {
+            self.count = 0;
+        }
+        self.last = gesture;
+        self.last_ms = now_ms;
+        (gesture, self.count)
+    }
+
+    /// Forget the previous gesture so that the next one starts a new series.
+    pub fn reset(&mut self) {
+        self.last = Gesture::None;
+        self.count = 0;
+    }
+}
@@ -0,0 +1,63 @@
+use gesture::decoder::Gesture;
+
+/// Suppresses spurious gestures by majority vote.
+///
+/// Keeps the last `N` decoded gestures and reports a gesture only when it
+/// makes up the majority of them, so that a single misread frame does not
+/// change the result.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GestureSmoother<const N: usize> {
+    gestures: [Gesture; N],
+    index: usize,
+    len: usize,
+}
+
+impl<const N: usize> Default for GestureSmoother<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> GestureSmoother<N> {
+    /// Create a new empty smoother.
+    pub fn new() -> Self {
+        GestureSmoother {
+            gestures: [Gesture::None; N],
+            index: 0,
+            len: 0,
+        }
+    }
+
+    /// Add a gesture and return the stable gesture, if any.
+    ///
+    /// A gesture is stable if it appears more than `N / 2` times among the last
+    /// `N` gestures. Until then, `None` is returned. With `N == 0` the gesture
+    /// is returned unfiltered.
+    ///
+    /// `Gesture::None` is not recorded and returns `None`, so this can be called
+    /// on every poll.
+    pub fn push(&mut self, gesture: Gesture) -> Option<Gesture> {
+        if gesture == Gesture::None {
+            return None;
+        }
+        if N == 0 {
+            return Some(gesture);
+        }
+        self.gestures[self.index] = gesture;
+        self.index = (self.index + 1) % N;
+        if self.len < N {
+            self.len += 1;
+        }
+        let recorded = &self.gestures[..self.len];
+        recorded
+            .iter()
+            .find(|&&candidate| recorded.iter().filter(|&&g| g == candidate).count() > N / 2)
+            .cloned()
+    }
+
+    /// Discard all recorded gestures.
+    pub fn reset(&mut self) {
+        self.index = 0;
+        self.len = 0;
+    }
+}
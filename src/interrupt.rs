@@ -0,0 +1,200 @@
+//! Interrupt handling.
+
+use hal::{digital::InputPin, i2c};
+use {
+    register::{self, GStatus},
+    Apds9960, BitFlags, Error, Register,
+};
+
+/// STATUS register contents.
+///
+/// Flags can be combined with `|` and tested with `contains()`:
+/// ```
+/// # use apds9960::Status;
+/// let status = Status::from(0b0010_0010);
+/// assert!(status.contains(Status::PVALID | Status::PINT));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Status(u8);
+
+impl Status {
+    /// Color / ambient light data is valid (AVALID).
+    pub const AVALID: Status = Status(register::Status::AVALID);
+    /// Proximity data is valid (PVALID).
+    pub const PVALID: Status = Status(register::Status::PVALID);
+    /// Gesture interrupt (GINT).
+    pub const GINT: Status = Status(register::Status::GINT);
+    /// Ambient light interrupt (AINT).
+    pub const AINT: Status = Status(register::Status::AINT);
+    /// Proximity interrupt (PINT).
+    pub const PINT: Status = Status(register::Status::PINT);
+    /// Proximity or gesture saturation (PGSAT).
+    pub const PGSAT: Status = Status(register::Status::PGSAT);
+    /// Clear photodiode saturation (CPSAT).
+    pub const CPSAT: Status = Status(register::Status::CPSAT);
+
+    /// No flag set.
+    pub fn empty() -> Self {
+        Status(0)
+    }
+
+    /// Raw STATUS register value.
+    pub fn bits(self) -> u8 {
+        self.0
+    }
+
+    /// Whether all the flags in `other` are also set in `self`.
+    pub fn contains(self, other: Status) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl core::ops::BitOr for Status {
+    type Output = Status;
+    fn bitor(self, rhs: Status) -> Status {
+        Status(self.0 | rhs.0)
+    }
+}
+
+impl core::ops::BitOrAssign for Status {
+    fn bitor_assign(&mut self, rhs: Status) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl From<u8> for Status {
+    /// The reserved bit 3 is ignored.
+    fn from(value: u8) -> Self {
+        Status(value & !0b0000_1000)
+    }
+}
+
+impl From<Status> for u8 {
+    fn from(status: Status) -> Self {
+        status.0
+    }
+}
+
+/// Interrupt sources read from the STATUS register.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct InterruptStatus {
+    /// Ambient light interrupt (AINT).
+    pub light: bool,
+    /// Proximity interrupt (PINT).
+    pub proximity: bool,
+    /// Gesture interrupt (GINT).
+    pub gesture: bool,
+    /// Proximity or gesture saturation (PGSAT).
+    pub proximity_saturation: bool,
+    /// Clear photodiode saturation (CPSAT).
+    pub clear_saturation: bool,
+}
+
+impl From<u8> for InterruptStatus {
+    fn from(value: u8) -> Self {
+        let status = Status::from(value);
+        InterruptStatus {
+            light: status.contains(Status::AINT),
+            proximity: status.contains(Status::PINT),
+            gesture: status.contains(Status::GINT),
+            proximity_saturation: status.contains(Status::PGSAT),
+            clear_saturation: status.contains(Status::CPSAT),
+        }
+    }
+}
+
+/// Data ready flags returned by [`data_ready()`](struct.Apds9960.html#method.data_ready).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DataReady {
+    /// Proximity data is available (PVALID).
+    pub proximity: bool,
+    /// Color / ambient light data is available (AVALID).
+    pub als: bool,
+    /// Gesture data is available in the FIFO (GVALID).
+    pub gesture: bool,
+}
+
+/// Pending event returned by [`poll()`](struct.Apds9960.html#method.poll).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// Gesture data is available in the FIFO (GVALID).
+    GestureReady,
+    /// Proximity data is available (PVALID).
+    ProximityReady,
+    /// Color / ambient light data is available (AVALID).
+    LightReady,
+    /// Proximity or gesture saturation occurred (PGSAT).
+    ProximitySaturation,
+    /// Clear photodiode saturation occurred (CPSAT).
+    ColorSaturation,
+}
+
+impl<I2C, E> Apds9960<I2C>
+where
+    I2C: i2c::I2c<Error = E>,
+{
+    /// Wait until the interrupt pin is asserted and read the interrupt sources.
+    ///
+    /// The INT line of the APDS9960 is active-low, so this blocks until
+    /// `int_pin` reads low and then reads the STATUS register.
+    /// The interrupts are not cleared.
+    ///
+    /// Returns `Error::Pin` if the pin cannot be read.
+    pub fn wait_for_interrupt<P: InputPin>(
+        &mut self,
+        int_pin: &mut P,
+    ) -> Result<InterruptStatus, Error<E>> {
+        while !int_pin.is_low().map_err(|_| Error::Pin)? {}
+        self.read_register(Register::STATUS)
+            .map(InterruptStatus::from)
+    }
+
+    /// Read the STATUS register.
+    pub fn read_status(&mut self) -> Result<Status, Error<E>> {
+        self.read_register(Register::STATUS).map(Status::from)
+    }
+
+    /// Read the STATUS and GSTATUS registers and return which data is ready.
+    ///
+    /// This combines [`is_proximity_data_valid()`](struct.Apds9960.html#method.is_proximity_data_valid),
+    /// [`is_light_data_valid()`](struct.Apds9960.html#method.is_light_data_valid) and
+    /// [`is_gesture_data_valid()`](struct.Apds9960.html#method.is_gesture_data_valid)
+    /// in a single call.
+    pub fn data_ready(&mut self) -> Result<DataReady, Error<E>> {
+        let status = self.read_status()?;
+        let gstatus = GStatus::create(self.read_register(Register::GSTATUS)?);
+        Ok(DataReady {
+            proximity: status.contains(Status::PVALID),
+            als: status.contains(Status::AVALID),
+            gesture: gstatus.is(GStatus::GVALID, true),
+        })
+    }
+
+    /// Read the STATUS and GSTATUS registers and return the highest-priority
+    /// pending event, if any.
+    ///
+    /// The priority order is the order of the [`Event`](enum.Event.html) variants:
+    /// data ready events come first since reading the data clears them, which lets
+    /// the other events come through on the next call. The saturation flags stay
+    /// set until cleared with
+    /// [`clear_proximity_interrupt()`](struct.Apds9960.html#method.clear_proximity_interrupt) or
+    /// [`clear_color_interrupt()`](struct.Apds9960.html#method.clear_color_interrupt).
+    pub fn poll(&mut self) -> Result<Option<Event>, Error<E>> {
+        let status = self.read_status()?;
+        let gstatus = GStatus::create(self.read_register(Register::GSTATUS)?);
+        let event = if gstatus.is(GStatus::GVALID, true) {
+            Some(Event::GestureReady)
+        } else if status.contains(Status::PVALID) {
+            Some(Event::ProximityReady)
+        } else if status.contains(Status::AVALID) {
+            Some(Event::LightReady)
+        } else if status.contains(Status::PGSAT) {
+            Some(Event::ProximitySaturation)
+        } else if status.contains(Status::CPSAT) {
+            Some(Event::ColorSaturation)
+        } else {
+            None
+        };
+        Ok(event)
+    }
+}
@@ -10,62 +10,137 @@
 //! - Enable/disable delay between proximity and / or color / ambient light cycles. See: [`enable_wait()`].
 //! - Enable/disable long delay between proximity and / or color / ambient light cycles. See: [`enable_wait_long()`].
 //! - Set the waiting time between proximity and / or color / ambient light cycles. See: [`set_wait_time()`].
+//! - Read the waiting time and whether long wait is enabled. See: [`get_wait_time_ms()`], [`get_wait_long()`].
+//! - Convert between wait cycles and WTIME register values. See: [`wtime_from_cycles()`], [`wtime_to_cycles()`].
 //! - Force an interrupt. See: [`force_interrupt()`].
+//! - Enable/disable sleep after interrupt. See: [`enable_sleep_after_interrupt()`], [`disable_sleep_after_interrupt()`], [`is_sleep_after_interrupt_enabled()`].
 //! - Clear all non-gesture interrupts. See: [`clear_interrupts()`].
+//! - Reset the device without power-cycling it. See: [`soft_reset()`].
+//! - Restore the power-on reset register values from the datasheet. See: [`reset_to_defaults()`].
+//! - Power the device down cleanly, e.g. before releasing it. See: [`shutdown()`].
+//! - Power the device down and return the I²C bus. See: [`release()`].
+//! - Initialize the device reading every register back to detect bus problems. See: [`init_verified()`].
+//! - Register values written by [`init()`] as public constants. See: [`defaults`].
+//! - Save/restore the configuration. See: [`get_config()`], [`restore_config()`].
 //! - Read the device ID. See: [`read_device_id()`].
+//! - Read/write a block of consecutive registers in a single transaction. See: [`read_registers()`], [`write_registers()`].
 //! - Get current sensor mode. See: [`get_mode()`].
 //! - Set sensor mode. See: [`set_mode()`].
+//! - Set/get sensor mode with typed flags. See: [`Mode`], [`set_mode_flags()`], [`get_mode_flags()`].
+//! - Decode the ENABLE register for diagnostics. See: [`get_enable_flags()`].
+//! - Read whether the device is powered and whether an engine is enabled. See: [`is_powered()`], [`is_engine_enabled()`].
+//! - Read the register values cached by the driver back from the device. See: [`refresh()`].
+//! - Set/get the blocking behavior of the data reading methods. See: [`set_read_mode()`], [`read_mode()`].
+//! - Log register accesses and gesture FIFO overflows with the [`log`](https://docs.rs/log) crate by enabling the `log` feature.
 //!
 //! ## Proximity Sensor
 //! - Enable/disable the proximity sensor. See: [`enable_proximity()`], [`disable_proximity()`].
+//! - Read the proximity photodiode offsets. See: [`get_proximity_offsets()`].
+//! - Apply a set of proximity engine settings, e.g. the low-power preset. See: [`apply_proximity_config()`], [`ProximityConfig`].
 //! - Enable/disable proximity interrupt generation. See: [`enable_proximity_interrupts()`], [`disable_proximity_interrupts()`].
 //! - Enable/disable proximity saturation interrupt generation. See: [`enable_proximity_saturation_interrupts()`].
+//! - Read whether a proximity saturation interrupt occurred. See: [`is_proximity_saturation_interrupt()`].
 //! - Read the proximity data. See: [`read_proximity()`].
+//! - Read the proximity data without waiting for it to be valid. See: [`read_proximity_raw()`].
+//! - Read the proximity data waiting with a delay until it is ready. See: [`read_proximity_blocking()`].
+//! - Read several consecutive proximity samples, e.g. for averaging. See: [`read_proximity_samples()`].
 //! - Check whether the proximity data is valid. See: [`is_proximity_data_valid()`].
+//! - Check whether the proximity measurement is saturated. See: [`is_proximity_saturated()`].
 //! - Set the proximity interrupt low/high thresholds. See: [`set_proximity_low_threshold()`], [`set_proximity_high_threshold()`].
 //! - Get proximity interrupt thresholds. See: [`get_proximity_low_threshold()`], [`get_proximity_high_threshold()`].
+//! - Set/get both proximity interrupt thresholds at once. See: [`set_proximity_thresholds()`], [`get_proximity_thresholds()`].
+//! - Set the proximity interrupt thresholds around the measured noise floor. See: [`auto_tune_proximity_thresholds()`].
 //! - Set the proximity offsets. See: [`set_proximity_offsets()`], [`set_proximity_up_right_offset()`], [`set_proximity_down_left_offset()`].
 //! - Clear proximity interrupt. See: [`clear_proximity_interrupt()`].
 //! - Set/get proximity gain. See: [`set_proximity_gain()`], [`get_proximity_gain()`].
 //! - Set/get LED drive strength. See: [`set_led_drive()`], [`get_led_drive()`].
+//! - Set/get proximity pulse count and length. See: [`set_proximity_pulse()`], [`get_proximity_pulse()`].
 //! - Set/get proximity gain compensation. See: [`enable_proximity_gain_compensation()`], [`disable_proximity_gain_compensation()`], [`get_proximity_gain_compensation()`].
 //! - Set/get proximity photodiode mask. See: [`set_proximity_photodiode_mask()`], [`get_proximity_photodiode_mask()`].
 //! - Set/get proximity interrupt persistence. See: [`set_proximity_interrupt_persistence()`], [`get_proximity_interrupt_persistence()`].
+//! - Estimate the proximity detection range. See: [`estimated_max_range()`].
+//! - Filter the proximity readings with a moving average. See: [`ProximityFilter`].
+//! - Track a slowly-adapting proximity baseline. See: [`BaselineTracker`], [`proximity_vs_baseline()`].
+//! - Convert proximity readings to an approximate distance. See: [`ProximityCurve`], [`proximity_to_cm()`].
 //!
 //! ## Color / Ambient Light Sensor
+//! - Set up and enable the color / ambient light sensor in one call. See: [`enable_light_sensor()`], [`disable_light_sensor()`].
+//! - Set up the device for accurate color measurement in one call. See: [`configure_color_meter()`].
 //! - Enable/disable the color / ambient light sensor. See: [`enable_light()`], [`disable_light()`].
 //! - Enable/disable ambient light interrupt generation. See: [`enable_light_interrupts()`], [`disable_light_interrupts()`].
 //! - Enable/disable ambient light saturation interrupt generation. See: [`enable_light_saturation_interrupts()`].
 //! - Check whether the color / ambient light data is valid. See: [`is_light_data_valid()`].
+//! - Check whether the clear channel is saturated. See: [`is_color_saturated()`].
 //! - Read the color / ambient light data. See: [`read_light()`], [`read_light_clear()`], [`read_light_red()`], [`read_light_green()`], [`read_light_blue()`].
+//! - Read the color / ambient light data together with a saturation flag. See: [`read_light_checked()`].
+//! - Read a single color / ambient light channel. See: [`Channel`], [`read_channel()`].
+//! - Take a quick ambient light sample with a short integration time. See: [`quick_ambient()`].
 //! - Set the color / ambient light integration time. See: [`set_light_integration_time()`], [`get_light_integration_time()`].
 //! - Set/get clear light channel interrupt low/high thresholds. See: [`set_light_low_threshold()`], [`set_light_high_threshold()`], [`get_light_low_threshold()`], [`get_light_high_threshold()`].
-//! - Clear ambient light interrupt. See: [`clear_light_interrupt()`].
+//! - Set/get both clear light channel interrupt thresholds at once. See: [`set_light_thresholds()`], [`get_light_thresholds()`].
+//! - Clear ambient light interrupt without clearing the proximity interrupt. See: [`clear_light_interrupt()`], [`clear_color_interrupt()`].
+//! - Read the color / ambient light data and clear the interrupt in an interrupt handler. See: [`read_and_clear_light_interrupt()`].
 //! - Set/get ambient light interrupt persistence. See: [`set_light_interrupt_persistence()`], [`get_light_interrupt_persistence()`].
-//! - Set/get color gain. See: [`set_color_gain()`], [`get_color_gain()`].
+//! - Set/get color gain. See: [`set_color_gain()`], [`get_color_gain()`] (also available as [`set_light_gain()`], [`get_light_gain()`]).
+//! - Automatically adjust the color gain to the light conditions. See: [`auto_adjust_light_gain()`].
+//! - Automatically adjust the integration time to the light conditions. See: [`auto_adjust_integration_time()`].
 //! - Check if color data is ready. See: [`is_color_data_ready()`].
+//! - Get chromaticity / normalized color values. See: [`LightData::chromaticity()`], [`LightData::normalized()`].
+//! - Check whether a reading is saturated. See: [`LightData::is_saturated()`].
+//! - Estimate the infrared level. See: [`LightData::ir()`].
+//! - Get the perceptual luminance of the color channels. See: [`LightData::luminance()`].
+//! - Apply a color correction matrix. See: [`LightData::apply_ccm()`].
+//! - Apply a set of color / ambient light engine settings. See: [`apply_light_config()`], [`LightConfig`].
+//! - Set up continuous color / ambient light and proximity monitoring with wait between cycles. See: [`setup_monitor()`].
+//! - Correct channel sensitivity differences. See: [`LightData::calibrated()`], [`ColorCalibration`].
+//! - Classify the ambient brightness, e.g. for display dimming. See: [`LightData::brightness_category()`], [`Brightness`].
+//! - Classify the dominant color, e.g. for color sorting. See: [`LightData::dominant_color()`], [`Color`].
+//! - Detect white and black surfaces. See: [`LightData::is_white()`], [`LightData::is_black()`].
+//! - Subtract a baseline reading and scale readings, e.g. after a gain change. See: `LightData - LightData`, [`LightData::scale()`].
+//! - Get gamma-corrected 8-bit color values. See: [`LightData::to_rgb8()`], [`LightData::to_rgb8_gamma()`].
+//! - Convert color readings to `rgb::RGB8` by enabling the `rgb` feature.
+//! - Get the maximum count for an integration time. See: [`max_count_for_integration_time()`].
+//! - Convert between integration cycles and ATIME register values. See: [`atime_from_cycles()`], [`atime_to_cycles()`].
 //!
 //! ## Gesture Detection
+//! - Set up and enable the gesture sensor (and the proximity engine it relies on) in one call. See: [`enable_gesture_sensor()`], [`disable_gesture_sensor()`].
 //! - Enable/disable gesture detection. See: [`enable_gesture()`], [`disable_gesture()`].
-//! - Enable/disable gesture mode. See: [`enable_gesture_mode()`], [`disable_gesture_mode()`].
+//! - Apply a set of gesture engine settings, e.g. the high sensitivity preset. See: [`apply_gesture_config()`], [`GestureConfig`].
 //! - Enable/disable gesture interrupts. See: [`enable_gesture_interrupts()`], [`disable_gesture_interrupts()`].
 //! - Read whether there is valid gesture data available. See: [`is_gesture_data_valid()`].
 //! - Read the amount of gesture data available. See: [`read_gesture_data_level()`].
 //! - Set the threshold of amount of available gesture data. See: [`set_gesture_data_level_threshold()`].
+//! - Set/Get the gesture FIFO level that generates an interrupt. See: [`set_gesture_fifo_interrupt_level()`], [`get_gesture_fifo_interrupt_level()`].
 //! - Read whether the gesture data has overflown. See: [`has_gesture_data_overflown()`].
+//! - Read the gesture data level and status at once. See: [`read_gesture_status()`].
 //! - Read the gesture data. See: [`read_gesture_data()`].
-//! - Set the gesture proximity entry/exit thresholds. See: [`set_gesture_proximity_entry_threshold()`], [`set_gesture_proximity_exit_threshold()`].
+//! - Read the raw gesture FIFO contents. See: [`read_gesture_fifo_all()`].
+//! - Set the gesture proximity entry/exit thresholds. See: [`set_gesture_proximity_entry_threshold()`], [`set_gesture_proximity_exit_threshold()`], [`set_gesture_proximity_thresholds()`].
 //! - Get gesture thresholds. See: [`get_gesture_proximity_entry_threshold()`], [`get_gesture_proximity_exit_threshold()`].
-//! - Set/get gesture gain. See: [`set_gesture_gain()`], [`get_gesture_gain()`].
+//! - Set/get gesture gain. See: [`set_gesture_gain()`], [`get_gesture_gain()`], [`get_gesture_gain_multiplier()`].
 //! - Set/get gesture LED drive. See: [`set_gesture_led_drive()`], [`get_gesture_led_drive()`].
+//! - Set/get gesture pulse count and length. See: [`set_gesture_pulse()`], [`get_gesture_pulse()`].
 //! - Set/get gesture wait time. See: [`set_gesture_wait_time()`], [`get_gesture_wait_time()`].
-//! - Set/get gesture offsets. See: [`set_gesture_offsets()`], [`set_gesture_up_offset()`], [`set_gesture_down_offset()`], [`set_gesture_left_offset()`], [`set_gesture_right_offset()`].
-//! - Set/get gesture mode. See: [`get_gesture_mode()`], [`set_gesture_mode()`].
-//! - Decode gesture with advanced algorithm. See: [`decode_gesture()`].
-//! - Set/get rotation. See: [`set_rotation()`], [`rotation()`].
+//! - Set gesture gain, LED drive and wait time at once. See: [`set_gesture_control()`].
+//! - Set/get gesture offsets. See: [`set_gesture_offsets()`], [`set_gesture_up_offset()`], [`set_gesture_down_offset()`], [`set_gesture_left_offset()`], [`set_gesture_right_offset()`], [`get_gesture_offsets()`].
+//! - Set/get gesture photodiode dimensions. See: [`set_gesture_dimensions()`], [`get_gesture_dimensions()`].
+//! - Set/get gesture exit persistence. See: [`set_gesture_exit_persistence()`], [`get_gesture_exit_persistence()`].
+//! - Set/get gesture exit mask. See: [`set_gesture_exit_mask()`], [`get_gesture_exit_mask()`].
+//! - Set/get gesture mode. See: [`get_gesture_mode()`], [`set_gesture_mode()`], [`enable_gesture_mode()`], [`disable_gesture_mode()`].
+//! - Decode gesture with advanced algorithm. See: [`decode_gesture()`], [`decode_gesture_with_velocity()`].
+//! - Decode a gesture with a confidence score and the number of datasets used. See: [`decode_gesture_detailed()`], [`GestureResult`].
+//! - Decode a gesture incrementally across several calls. See: [`feed_gesture()`].
+//! - Decode gesture with a custom algorithm. See: [`GestureDecoder`], [`decode_gesture_with()`].
+//! - Get the direction of a gesture as an angle, e.g. to rotate an icon. See: [`Gesture::angle()`].
+//! - Set/get the gesture decoding parameters. See: [`set_gesture_decode_params()`], [`gesture_decode_params()`].
+//! - Set/get the maximum number of datasets used by the gesture decoding. See: [`set_gesture_max_datasets()`], [`gesture_max_datasets()`].
+//! - Set/get rotation. See: [`set_rotation()`], [`set_rotation_enum()`], [`rotation()`].
 //! - Clear gesture FIFO. See: [`clear_gesture_fifo()`].
 //! - Check if gesture is available. See: [`is_gesture_available()`].
 //! - Read gesture with FIFO processing. See: [`read_gesture()`].
+//! - Decode a gesture with a bounded wait. See: [`decode_gesture_timeout()`].
+//! - Detect quickly repeated gestures. See: [`RepeatGestureDetector`].
+//! - Suppress spurious single-frame gestures by majority vote. See: [`GestureSmoother`].
 //!
 //! ## Power Management
 //! - Enable/disable power. See: [`enable_power()`], [`disable_power()`].
@@ -76,6 +151,22 @@
 //! - Clear ambient light interrupt. See: [`clear_light_interrupt()`].
 //! - Clear proximity interrupt. See: [`clear_proximity_interrupt()`].
 //! - Clear gesture interrupt. See: [`clear_gesture_interrupt()`].
+//! - Wait for the INT pin and read the interrupt sources. See: [`wait_for_interrupt()`].
+//! - Poll for the highest-priority pending event in a simple event loop. See: [`Event`], [`poll()`].
+//! - Read which proximity, color / ambient light and gesture data is ready at once. See: [`DataReady`], [`data_ready()`].
+//! - Read all STATUS register flags. See: [`Status`], [`read_status()`].
+//! - Set/get ambient light and proximity interrupt persistence at once. See: [`set_interrupt_persistence()`], [`get_interrupt_persistence()`].
+//!
+//! ## Calibration
+//! - Calibrate the proximity offsets. See: [`calibrate_proximity_offsets()`].
+//! - Calibrate the gesture offsets. See: [`calibrate_gesture_offsets()`].
+//! - Take a white reference reading. See: [`read_white_reference()`].
+//! - Run a guided calibration of everything. See: [`calibrate_all()`].
+//! - Apply calibration results. See: [`apply_calibration()`].
+//!
+//! ## Raw Register Access
+//! - Read/modify arbitrary register fields (requires the `unstable-raw` feature).
+//!   See: [`field()`], [`set_field()`].
 //!
 //! [`enable()`]: struct.Apds9960.html#method.enable
 //! [`disable()`]: struct.Apds9960.html#method.disable
@@ -184,7 +275,37 @@
 
 extern crate embedded_hal as hal;
 use hal::i2c;
+extern crate libm;
 extern crate nb;
+#[cfg(feature = "log")]
+#[macro_use]
+extern crate log;
+#[cfg(feature = "rgb")]
+extern crate rgb;
+
+/// Log with `trace!()` if the `log` feature is enabled.
+macro_rules! log_trace {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "log")]
+        trace!($($arg)*);
+    };
+}
+
+/// Log with `debug!()` if the `log` feature is enabled.
+macro_rules! log_debug {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "log")]
+        debug!($($arg)*);
+    };
+}
+
+/// Log with `warn!()` if the `log` feature is enabled.
+macro_rules! log_warn {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "log")]
+        warn!($($arg)*);
+    };
+}
 
 /// All possible errors in this crate
 #[derive(Debug)]
@@ -193,6 +314,51 @@ pub enum Error<E> {
     I2C(E),
     /// Invalid rotation value was supplied to the gesture decoder.
     InvalidRotation,
+    /// The data did not become ready in time.
+    Timeout,
+    /// Invalid input data provided (e.g. a low threshold above the high threshold, or an
+    /// empty buffer). Single values outside of their supported range are reported as
+    /// `InvalidParameter` instead.
+    InvalidInputData,
+    /// Error reading the interrupt pin.
+    Pin,
+    /// A register did not contain the value written to it when read back.
+    VerifyFailed {
+        /// Register address.
+        register: u8,
+        /// Value written.
+        expected: u8,
+        /// Value read back.
+        actual: u8,
+    },
+    /// Most of the gesture datasets were saturated, e.g. because the hand was too close.
+    GestureSaturated,
+    /// A parameter is outside of its supported range (e.g. 64 pulses or an integration
+    /// time above 711.68ms).
+    InvalidParameter,
+}
+
+impl<E> core::fmt::Display for Error<E> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            Error::I2C(_) => f.write_str("I²C bus error"),
+            Error::InvalidRotation => f.write_str("invalid rotation value"),
+            Error::Timeout => f.write_str("timed out waiting for data"),
+            Error::InvalidInputData => f.write_str("invalid input data"),
+            Error::Pin => f.write_str("interrupt pin error"),
+            Error::VerifyFailed {
+                register,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "register 0x{:02X} verification failed: wrote 0x{:02X}, read 0x{:02X}",
+                register, expected, actual
+            ),
+            Error::GestureSaturated => f.write_str("gesture photodiodes saturated"),
+            Error::InvalidParameter => f.write_str("parameter out of range"),
+        }
+    }
 }
 
 /// Gesture FIFO data threshold.
@@ -210,8 +376,25 @@ pub enum GestureDataThreshold {
     Th16,
 }
 
+/// Behavior of the data reading methods when the data is not ready yet.
+///
+/// This applies to [`read_proximity()`](struct.Apds9960.html#method.read_proximity),
+/// [`read_light()`](struct.Apds9960.html#method.read_light) and the
+/// `read_light_*()` channel methods.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ReadMode {
+    /// Return `nb::Error::WouldBlock` if the data is not ready. (default)
+    #[default]
+    NonBlocking,
+    /// Keep polling the device until the data is ready.
+    Blocking,
+    /// Poll the device up to the given number of times and return
+    /// `Error::Timeout` if the data is still not ready.
+    Timeout(u32),
+}
+
 /// Color / ambient light data.
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct LightData {
     /// Clear channel value.
     pub clear: u16,
@@ -348,6 +531,7 @@ mod register {
     pub struct Config3(u8);
     impl Config3 {
         pub const PCMP: u8 = 0b0010_0000;
+        pub const SAI: u8 = 0b0001_0000;
         pub const PMASK_R: u8 = 0b0000_0001;
         pub const PMASK_L: u8 = 0b0000_0010;
         pub const PMASK_D: u8 = 0b0000_0100;
@@ -393,6 +577,9 @@ mod register {
     impl GConfig1 {
         pub const GFIFOTH1: u8 = 0b1000_0000;
         pub const GFIFOTH0: u8 = 0b0100_0000;
+        pub const GEXMSK_SHIFT: u8 = 2;
+        pub const GEXMSK_MASK: u8 = 0b0011_1100;
+        pub const GEXPERS_MASK: u8 = 0b0000_0011;
     }
     impl_bitflags!(GConfig1, GCONF1);
 
@@ -401,6 +588,11 @@ mod register {
     impl Status {
         pub const AVALID: u8 = 0b0000_0001;
         pub const PVALID: u8 = 0b0000_0010;
+        pub const GINT: u8 = 0b0000_0100;
+        pub const AINT: u8 = 0b0001_0000;
+        pub const PINT: u8 = 0b0010_0000;
+        pub const PGSAT: u8 = 0b0100_0000;
+        pub const CPSAT: u8 = 0b1000_0000;
     }
     impl_bitflags!(Status, STATUS);
 
@@ -434,7 +626,11 @@ pub struct Apds9960<I2C> {
     pers: register::Pers,
     gconfig1: register::GConfig1,
     gconfig4: register::GConfig4,
-    rotation: u16,
+    rotation: Rotation,
+    read_mode: ReadMode,
+    gesture_decode_params: GestureDecodeParams,
+    gesture_accumulator: gesture::decoder::GestureAccumulator,
+    gesture_max_datasets: u8,
 }
 
 impl<I2C, E> Apds9960<I2C>
@@ -452,15 +648,32 @@ where
             pers: register::Pers::default(),
             gconfig1: register::GConfig1::default(),
             gconfig4: register::GConfig4::default(),
-            rotation: 0,
+            rotation: Rotation::Deg0,
+            read_mode: ReadMode::default(),
+            gesture_decode_params: GestureDecodeParams::default(),
+            gesture_accumulator: gesture::decoder::GestureAccumulator::default(),
+            gesture_max_datasets: 32,
         }
     }
 
     /// Destroy driver instance, return I²C bus instance.
+    ///
+    /// The device is left in its current state. See also [`release()`](#method.release).
     pub fn destroy(self) -> I2C {
         self.i2c
     }
 
+    /// Power the device down and return the I²C bus instance.
+    ///
+    /// This runs [`shutdown()`](#method.shutdown) first so that the device is not
+    /// left measuring and drawing LED current. The bus is returned even if the
+    /// shutdown fails, since the driver instance cannot be used anymore anyway.
+    pub fn release(mut self) -> I2C {
+        // Nothing sensible can be done about an error at this point.
+        let _ = self.shutdown();
+        self.i2c
+    }
+
     /// Probe the I2C bus to check if the device is present at the expected address.
     ///
     /// This method performs a zero-length write operation to the device address,
@@ -521,11 +734,36 @@ where
     }
 }
 
+mod calibration;
 mod config;
+pub mod defaults;
 mod gains;
 mod gesture;
+mod interrupt;
 mod light;
 mod proximity;
+#[cfg(feature = "unstable-raw")]
+mod raw;
 mod reading;
 
-pub use gains::{GestureGain, LedBoost, LedDrive, LightGain, ProximityGain};
+pub use calibration::{CalibrationBlob, CalibrationStep};
+pub use config::{wtime_from_cycles, wtime_to_cycles, ConfigSnapshot, EnableFlags, Engine, Mode};
+pub use gains::{GestureGain, GestureWaitTime, LedBoost, LedDrive, LightGain, ProximityGain};
+pub use gesture::{
+    config::GestureConfig,
+    decoder::{
+        DefaultGestureDecoder, Gesture, GestureDecodeParams, GestureDecoder, GestureResult,
+        Rotation,
+    },
+    reading::GestureStatus,
+    repeat::RepeatGestureDetector,
+    smoother::GestureSmoother,
+};
+pub use interrupt::{DataReady, Event, InterruptStatus, Status};
+pub use light::{
+    atime_from_cycles, atime_to_cycles, max_count_for_integration_time, Brightness, Channel, Color,
+    ColorCalibration, LightConfig,
+};
+pub use proximity::{
+    proximity_to_cm, BaselineTracker, ProximityConfig, ProximityCurve, ProximityFilter, RangeClass,
+};
@@ -1,9 +1,420 @@
-use hal::i2c;
+use core::cell::Cell;
+use core::ops::Sub;
+use hal::{delay::DelayNs, i2c};
 use {
+    defaults,
     register::{Config2, Enable, Pers, Status},
-    Apds9960, BitFlags, Error, LightData, Register,
+    Apds9960, BitFlags, Error, LightData, LightGain, Mode, ProximityConfig, Register,
 };
 
+/// Maximum number of gain changes done by `auto_adjust_light_gain()`.
+const AUTO_GAIN_MAX_ITERATIONS: u8 = 8;
+
+/// Maximum number of integration time changes done by `auto_adjust_integration_time()`.
+const AUTO_INTEGRATION_MAX_ITERATIONS: u8 = 8;
+
+/// ATIME value used by `quick_ambient()` (10 cycles = 27.8ms).
+const QUICK_AMBIENT_ATIME: u8 = 0xF6;
+
+/// Maximum number of data-valid polls done by `quick_ambient()`.
+const QUICK_AMBIENT_MAX_POLLS: u8 = 4;
+
+impl LightData {
+    /// Red, green and blue values normalized by their sum.
+    ///
+    /// The three values add up to 1.0 and are independent of the brightness, which
+    /// makes them useful for color classification.
+    /// If all three channels are zero, `(0.0, 0.0, 0.0)` is returned.
+    pub fn chromaticity(&self) -> (f32, f32, f32) {
+        let sum = f32::from(self.red) + f32::from(self.green) + f32::from(self.blue);
+        if sum == 0.0 {
+            return (0.0, 0.0, 0.0);
+        }
+        (
+            f32::from(self.red) / sum,
+            f32::from(self.green) / sum,
+            f32::from(self.blue) / sum,
+        )
+    }
+
+    /// Red, green and blue values normalized by the clear channel value.
+    ///
+    /// If the clear channel is zero, `[0.0, 0.0, 0.0]` is returned.
+    pub fn normalized(&self) -> [f32; 3] {
+        if self.clear == 0 {
+            return [0.0; 3];
+        }
+        let clear = f32::from(self.clear);
+        [
+            f32::from(self.red) / clear,
+            f32::from(self.green) / clear,
+            f32::from(self.blue) / clear,
+        ]
+    }
+
+    /// Approximate infrared level.
+    ///
+    /// The clear channel also measures infrared light, so this is estimated as
+    /// `clear - (red + green + blue)`, or 0 if the color channels add up to more
+    /// than the clear channel. A high IR level relative to the clear channel
+    /// indicates e.g. incandescent light or sunlight as opposed to LED lighting.
+    pub fn ir(&self) -> u16 {
+        let rgb = u32::from(self.red) + u32::from(self.green) + u32::from(self.blue);
+        u32::from(self.clear).saturating_sub(rgb) as u16
+    }
+
+    /// Red, green and blue values normalized by the clear channel and gamma-encoded
+    /// to 8 bits.
+    ///
+    /// Each channel value `v` (relative to the clear channel and limited to 1.0) is
+    /// converted to `255 * v^(1 / gamma)`, which gives perceptually more reasonable
+    /// values for display than a linear scaling. With a gamma of 1.0 the scaling is linear.
+    /// If the clear channel is zero, black (`[0, 0, 0]`) is returned.
+    pub fn to_rgb8_gamma(&self, gamma: f32) -> [u8; 3] {
+        if self.clear == 0 {
+            return [0; 3];
+        }
+        let encode = |value: f32| {
+            let value = if value > 1.0 { 1.0 } else { value };
+            (libm::powf(value, 1.0 / gamma) * 255.0 + 0.5) as u8
+        };
+        let [red, green, blue] = self.normalized();
+        [encode(red), encode(green), encode(blue)]
+    }
+
+    /// Red, green and blue values normalized by the clear channel and gamma-encoded
+    /// to 8 bits using a gamma of 2.2.
+    ///
+    /// See [`to_rgb8_gamma()`](#method.to_rgb8_gamma).
+    pub fn to_rgb8(&self) -> [u8; 3] {
+        self.to_rgb8_gamma(2.2)
+    }
+
+    /// Whether any channel reached the maximum count for the integration time.
+    ///
+    /// `atime_cycles` is the ATIME register value the data was measured with
+    /// (see [`set_light_integration_time()`](struct.Apds9960.html#method.set_light_integration_time)).
+    /// The maximum count is `min(65535, (256 - ATIME) * 1025)`.
+    /// Saturated readings do not reflect the actual light intensity.
+    pub fn is_saturated(&self, atime_cycles: u8) -> bool {
+        let max = max_count_for_integration_time(atime_cycles);
+        self.clear >= max || self.red >= max || self.green >= max || self.blue >= max
+    }
+
+    /// Ambient brightness category, e.g. for automatic display dimming.
+    ///
+    /// `atime` and `gain` are the integration time (ATIME register value) and gain the
+    /// data was measured with. The clear channel is normalized to counts per millisecond
+    /// of integration time at 1x gain, which is then compared against the thresholds
+    /// defined in [`Brightness`]. This is a raw count, not a lux value.
+    pub fn brightness_category(&self, atime: u8, gain: LightGain) -> Brightness {
+        let integration_ms = f32::from(atime_to_cycles(atime)) * 2.78;
+        let normalized = f32::from(self.clear) / (integration_ms * light_gain_factor(gain));
+        if normalized < Brightness::DIM_THRESHOLD {
+            Brightness::Dark
+        } else if normalized < Brightness::INDOOR_THRESHOLD {
+            Brightness::Dim
+        } else if normalized < Brightness::BRIGHT_THRESHOLD {
+            Brightness::Indoor
+        } else if normalized < Brightness::SUNLIGHT_THRESHOLD {
+            Brightness::Bright
+        } else {
+            Brightness::Sunlight
+        }
+    }
+
+    /// Dominant color of the light, e.g. for simple color sorting.
+    ///
+    /// The red, green and blue channels are normalized by their sum (see
+    /// [`chromaticity()`](#method.chromaticity)) and compared against each other:
+    /// - If all three channels are within [`Color::MARGIN`] of each other, `White` is returned.
+    /// - If one channel exceeds the other two by at least [`Color::MARGIN`], its color is returned.
+    /// - If all channels are zero (darkness) or two channels are similarly high
+    ///   (e.g. yellow), `Unknown` is returned.
+    pub fn dominant_color(&self) -> Color {
+        if self.red == 0 && self.green == 0 && self.blue == 0 {
+            return Color::Unknown;
+        }
+        let (red, green, blue) = self.chromaticity();
+        let max = red.max(green).max(blue);
+        let min = red.min(green).min(blue);
+        if max - min < Color::MARGIN {
+            return Color::White;
+        }
+        let second = red + green + blue - max - min;
+        if max - second < Color::MARGIN {
+            Color::Unknown
+        } else if max == red {
+            Color::Red
+        } else if max == green {
+            Color::Green
+        } else {
+            Color::Blue
+        }
+    }
+
+    /// Whether the light is white, e.g. for line-follower style surface detection.
+    ///
+    /// This is the case if each of the red, green and blue channels normalized by the
+    /// clear channel (see [`normalized()`](#method.normalized)) is at least `threshold`
+    /// and the channels are balanced (see [`dominant_color()`](#method.dominant_color)).
+    /// If the clear channel is zero, `false` is returned.
+    pub fn is_white(&self, threshold: f32) -> bool {
+        if self.clear == 0 {
+            return false;
+        }
+        let [red, green, blue] = self.normalized();
+        red >= threshold
+            && green >= threshold
+            && blue >= threshold
+            && self.dominant_color() == Color::White
+    }
+
+    /// Whether the light is black, i.e. the clear channel is below `threshold`.
+    ///
+    /// The raw clear channel count is compared, so the threshold depends on the
+    /// integration time and gain used.
+    pub fn is_black(&self, threshold: u16) -> bool {
+        self.clear < threshold
+    }
+
+    /// Perceptual luminance computed from the red, green and blue channels.
+    ///
+    /// This is `0.2126 * red + 0.7152 * green + 0.0722 * blue` (Rec. 709 weights) in
+    /// channel counts, like the channel values themselves. Unlike the clear channel it
+    /// is not affected by infrared light, which makes it useful for grayscale
+    /// conversion and exposure decisions. Gray light gives the same value as each of
+    /// its channels.
+    pub fn luminance(&self) -> f32 {
+        0.2126 * f32::from(self.red)
+            + 0.7152 * f32::from(self.green)
+            + 0.0722 * f32::from(self.blue)
+    }
+
+    /// Multiply the red, green and blue channels with a color correction matrix.
+    ///
+    /// The matrix is given in row-major order and is multiplied with the column
+    /// vector `[red, green, blue]` of raw channel counts, so the result is
+    /// `[r', g', b']` with `r' = m[0][0] * red + m[0][1] * green + m[0][2] * blue`
+    /// and so on. The matrix is typically obtained by characterizing the sensor
+    /// against a reference.
+    pub fn apply_ccm(&self, matrix: &[[f32; 3]; 3]) -> [f32; 3] {
+        let rgb = [
+            f32::from(self.red),
+            f32::from(self.green),
+            f32::from(self.blue),
+        ];
+        let mut out = [0.0; 3];
+        for (value, row) in out.iter_mut().zip(matrix.iter()) {
+            *value = row[0] * rgb[0] + row[1] * rgb[1] + row[2] * rgb[2];
+        }
+        out
+    }
+
+    /// Multiply all channels by `factor`, e.g. to compensate for a gain change.
+    ///
+    /// The results are rounded and saturate at 0 and 65535.
+    pub fn scale(&self, factor: f32) -> LightData {
+        self.calibrated(&ColorCalibration {
+            r_gain: factor,
+            g_gain: factor,
+            b_gain: factor,
+            c_gain: factor,
+        })
+    }
+
+    /// Multiply each channel by its calibration coefficient, e.g. to correct for
+    /// sensitivity differences between the channels.
+    ///
+    /// The results are rounded and saturate at 0 and 65535.
+    pub fn calibrated(&self, cal: &ColorCalibration) -> LightData {
+        // Float to integer casts saturate (and convert NaN to 0).
+        let scale = |value: u16, gain: f32| (f32::from(value) * gain + 0.5) as u16;
+        LightData {
+            clear: scale(self.clear, cal.c_gain),
+            red: scale(self.red, cal.r_gain),
+            green: scale(self.green, cal.g_gain),
+            blue: scale(self.blue, cal.b_gain),
+        }
+    }
+}
+
+impl Sub for LightData {
+    type Output = LightData;
+
+    /// Subtract another reading channel by channel, e.g. a dark-frame baseline.
+    ///
+    /// The results saturate at 0.
+    fn sub(self, other: LightData) -> LightData {
+        LightData {
+            clear: self.clear.saturating_sub(other.clear),
+            red: self.red.saturating_sub(other.red),
+            green: self.green.saturating_sub(other.green),
+            blue: self.blue.saturating_sub(other.blue),
+        }
+    }
+}
+
+/// Ambient brightness category.
+///
+/// The categories are defined by thresholds on the raw clear channel count, not on
+/// illuminance. How many counts a given illuminance produces depends on the light
+/// source spectrum, the cover glass and the individual device, so the names only
+/// describe the order of the categories. Calibrate against a lux meter if actual
+/// illuminance levels are needed.
+///
+/// See [`LightData::brightness_category()`](struct.LightData.html#method.brightness_category).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Brightness {
+    /// Below [`DIM_THRESHOLD`](#associatedconstant.DIM_THRESHOLD).
+    Dark,
+    /// From [`DIM_THRESHOLD`](#associatedconstant.DIM_THRESHOLD) up to
+    /// [`INDOOR_THRESHOLD`](#associatedconstant.INDOOR_THRESHOLD).
+    Dim,
+    /// From [`INDOOR_THRESHOLD`](#associatedconstant.INDOOR_THRESHOLD) up to
+    /// [`BRIGHT_THRESHOLD`](#associatedconstant.BRIGHT_THRESHOLD).
+    Indoor,
+    /// From [`BRIGHT_THRESHOLD`](#associatedconstant.BRIGHT_THRESHOLD) up to
+    /// [`SUNLIGHT_THRESHOLD`](#associatedconstant.SUNLIGHT_THRESHOLD).
+    Bright,
+    /// [`SUNLIGHT_THRESHOLD`](#associatedconstant.SUNLIGHT_THRESHOLD) and above.
+    Sunlight,
+}
+
+impl Brightness {
+    /// Normalized clear channel value (counts per ms at 1x gain) from which on the
+    /// light is considered `Dim`.
+    pub const DIM_THRESHOLD: f32 = 0.1;
+    /// Normalized clear channel value (counts per ms at 1x gain) from which on the
+    /// light is considered `Indoor`.
+    pub const INDOOR_THRESHOLD: f32 = 1.0;
+    /// Normalized clear channel value (counts per ms at 1x gain) from which on the
+    /// light is considered `Bright`.
+    pub const BRIGHT_THRESHOLD: f32 = 10.0;
+    /// Normalized clear channel value (counts per ms at 1x gain) from which on the
+    /// light is considered `Sunlight`.
+    pub const SUNLIGHT_THRESHOLD: f32 = 100.0;
+}
+
+/// Per-channel calibration coefficients.
+///
+/// See [`LightData::calibrated()`](struct.LightData.html#method.calibrated).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorCalibration {
+    /// Red channel coefficient. (default: 1.0)
+    pub r_gain: f32,
+    /// Green channel coefficient. (default: 1.0)
+    pub g_gain: f32,
+    /// Blue channel coefficient. (default: 1.0)
+    pub b_gain: f32,
+    /// Clear channel coefficient. (default: 1.0)
+    pub c_gain: f32,
+}
+
+impl ColorCalibration {
+    /// Calibration which leaves all channels unchanged.
+    pub fn identity() -> Self {
+        ColorCalibration {
+            r_gain: 1.0,
+            g_gain: 1.0,
+            b_gain: 1.0,
+            c_gain: 1.0,
+        }
+    }
+}
+
+impl Default for ColorCalibration {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+/// Color / ambient light engine settings applied together with
+/// [`apply_light_config()`](struct.Apds9960.html#method.apply_light_config).
+///
+/// The default values are the ones written by `init()`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LightConfig {
+    /// Color / ambient light gain. (default: 4x)
+    pub gain: LightGain,
+    /// Integration time as 2's complement of the number of cycles, see
+    /// [`set_light_integration_time()`](struct.Apds9960.html#method.set_light_integration_time).
+    /// (default: 0, 256 cycles)
+    pub integration_time: u8,
+}
+
+impl Default for LightConfig {
+    fn default() -> Self {
+        LightConfig {
+            gain: LightGain::X4,
+            integration_time: defaults::ATIME,
+        }
+    }
+}
+
+/// Dominant color.
+///
+/// See [`LightData::dominant_color()`](struct.LightData.html#method.dominant_color).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    /// Red is dominant.
+    Red,
+    /// Green is dominant.
+    Green,
+    /// Blue is dominant.
+    Blue,
+    /// All channels are balanced.
+    White,
+    /// No light or no single dominant channel.
+    Unknown,
+}
+
+impl Color {
+    /// Minimum difference between normalized channels (each between 0.0 and 1.0,
+    /// adding up to 1.0) for them to be considered different.
+    pub const MARGIN: f32 = 0.1;
+}
+
+#[cfg(feature = "rgb")]
+impl From<LightData> for rgb::RGB8 {
+    /// Scale the red, green and blue channels to 8 bits relative to the clear channel.
+    ///
+    /// This way, dim and bright light of the same color result in similar values.
+    /// If the clear channel is zero, black is returned.
+    fn from(data: LightData) -> Self {
+        if data.clear == 0 {
+            return rgb::RGB8::new(0, 0, 0);
+        }
+        let clear = u32::from(data.clear);
+        let scale = |value: u16| core::cmp::min(u32::from(value) * 255 / clear, 255) as u8;
+        rgb::RGB8::new(scale(data.red), scale(data.green), scale(data.blue))
+    }
+}
+
+/// Color / ambient light channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Channel {
+    /// Clear channel (CDATAL/CDATAH).
+    Clear,
+    /// Red channel (RDATAL/RDATAH).
+    Red,
+    /// Green channel (GDATAL/GDATAH).
+    Green,
+    /// Blue channel (BDATAL/BDATAH).
+    Blue,
+}
+
+impl Channel {
+    fn register(self) -> u8 {
+        match self {
+            Channel::Clear => Register::CDATAL,
+            Channel::Red => Register::RDATAL,
+            Channel::Green => Register::GDATAL,
+            Channel::Blue => Register::BDATAL,
+        }
+    }
+}
+
 /// Color and ambient light.
 impl<I2C, E> Apds9960<I2C>
 where
@@ -19,6 +430,83 @@ where
         self.set_flag_enable(Enable::AEN, false)
     }
 
+    /// Enable color and ambient light sensor with interrupts
+    pub fn enable_light_sensor(&mut self, interrupts: bool) -> Result<(), Error<E>> {
+        self.set_light_gain(LightGain::X4)?; // Default 4x gain
+        if interrupts {
+            self.enable_light_interrupts()?;
+        } else {
+            self.disable_light_interrupts()?;
+        }
+        self.enable()?;
+        self.enable_light()
+    }
+
+    /// Disable color and ambient light sensor
+    pub fn disable_light_sensor(&mut self) -> Result<(), Error<E>> {
+        self.disable_light_interrupts()?;
+        self.disable_light()
+    }
+
+    /// Set up the device as a color meter.
+    ///
+    /// Proximity, gesture detection and wait are disabled, as well as all
+    /// interrupts, including the proximity saturation and gesture interrupts,
+    /// so that the INT pin only reflects the color measurement. Then the integration
+    /// time is set to the number of cycles closest to `integration_ms` (one cycle is
+    /// 2.78ms), the gain is set and color / ambient light detection is enabled.
+    ///
+    /// Returns `Error::InvalidParameter` if `integration_ms` is outside of the
+    /// supported range of 2.78ms to 711.68ms. In this case nothing is written.
+    pub fn configure_color_meter(
+        &mut self,
+        integration_ms: f32,
+        gain: LightGain,
+    ) -> Result<(), Error<E>> {
+        // NaN is also rejected here as all comparisons with it are false.
+        if !(2.78..=711.68).contains(&integration_ms) {
+            return Err(Error::InvalidParameter);
+        }
+        let cycles = (integration_ms / 2.78 + 0.5) as u32;
+        self.set_mode_flags(Mode::POWER_ON)?;
+        // Both saturation interrupts are disabled with a single CONFIG2 write.
+        self.set_flag_config2(Config2::CPSIEN | Config2::PSIEN, false)?;
+        self.disable_gesture_interrupts()?;
+        self.set_light_integration_time(atime_from_cycles(cycles as u16))?;
+        self.set_light_gain(gain)?;
+        self.enable_light()
+    }
+
+    /// Apply the color / ambient light engine settings.
+    pub fn apply_light_config(&mut self, config: &LightConfig) -> Result<(), Error<E>> {
+        self.set_light_integration_time(config.integration_time)?;
+        self.set_light_gain(config.gain)
+    }
+
+    /// Set up the device for continuous color / ambient light and proximity monitoring.
+    ///
+    /// Both engine configurations are applied, wait between cycles is enabled to
+    /// save power and then color / ambient light detection, proximity detection and
+    /// the device are enabled. The duration of the wait is taken from the proximity
+    /// configuration, its `wait` setting is ignored.
+    ///
+    /// Returns `Error::InvalidParameter` if the proximity pulse count or length is out
+    /// of range. In this case nothing is written.
+    pub fn setup_monitor(
+        &mut self,
+        light: LightConfig,
+        proximity: ProximityConfig,
+    ) -> Result<(), Error<E>> {
+        self.apply_proximity_config(&ProximityConfig {
+            wait: true,
+            ..proximity
+        })?;
+        self.apply_light_config(&light)?;
+        self.enable_light()?;
+        self.enable_proximity()?;
+        self.enable()
+    }
+
     /// Set the color and ambient light integration time.
     ///
     /// The value parameter must be a 2's complement of the number of cycles.
@@ -64,15 +552,50 @@ where
         self.write_double_register(Register::AIHTL, threshold)
     }
 
+    /// Get the clear channel ambient light interrupt low threshold.
+    pub fn get_light_low_threshold(&mut self) -> Result<u16, Error<E>> {
+        self.read_double_register(Register::AILTL)
+    }
+
+    /// Get the clear channel ambient light interrupt high threshold.
+    pub fn get_light_high_threshold(&mut self) -> Result<u16, Error<E>> {
+        self.read_double_register(Register::AIHTL)
+    }
+
+    /// Set the clear channel ambient light interrupt low and high thresholds.
+    ///
+    /// Returns `Error::InvalidInputData` if `low` is greater than `high`.
+    pub fn set_light_thresholds(&mut self, low: u16, high: u16) -> Result<(), Error<E>> {
+        if low > high {
+            return Err(Error::InvalidInputData);
+        }
+        self.set_light_low_threshold(low)?;
+        self.set_light_high_threshold(high)
+    }
+
+    /// Get the clear channel ambient light interrupt low and high thresholds.
+    ///
+    /// Returns `(low, high)`.
+    pub fn get_light_thresholds(&mut self) -> Result<(u16, u16), Error<E>> {
+        let low = self.get_light_low_threshold()?;
+        let high = self.get_light_high_threshold()?;
+        Ok((low, high))
+    }
+
     /// Set ambient light interrupt persistence.
     ///
     /// This value controls how many consecutive out-of-threshold measurements
     /// are required before triggering an ambient light interrupt.
     ///
     /// * `cycles`: Number of consecutive cycles (0-15)
+    ///
+    /// Returns `Error::InvalidParameter` if `cycles` is greater than 15.
     pub fn set_light_interrupt_persistence(&mut self, cycles: u8) -> Result<(), Error<E>> {
+        if cycles > 0x0F {
+            return Err(Error::InvalidParameter);
+        }
         let mut pers = self.read_register(Register::PERS)?;
-        pers = (pers & !Pers::APERS_MASK) | (cycles & 0x0F);
+        pers = (pers & !Pers::APERS_MASK) | cycles;
         self.write_register(Register::PERS, pers)
     }
 
@@ -87,17 +610,62 @@ where
         self.touch_register(Register::CICLEAR)
     }
 
+    /// Clear the color / ambient light interrupt (CICLEAR).
+    ///
+    /// This is the same as
+    /// [`clear_light_interrupt()`](struct.Apds9960.html#method.clear_light_interrupt).
+    /// Unlike [`clear_interrupts()`](struct.Apds9960.html#method.clear_interrupts),
+    /// which uses AICLEAR, a pending proximity interrupt is left untouched.
+    pub fn clear_color_interrupt(&mut self) -> Result<(), Error<E>> {
+        self.clear_light_interrupt()
+    }
+
+    /// Read the color / ambient light sensor data and clear the color / ambient
+    /// light interrupt afterwards.
+    ///
+    /// This is meant for interrupt handlers: the data valid flag is not checked
+    /// since the interrupt already signals new data.
+    pub fn read_and_clear_light_interrupt(&mut self) -> Result<LightData, Error<E>> {
+        let data = self.read_light_data()?;
+        self.clear_light_interrupt()?;
+        Ok(data)
+    }
+
     /// Read the color / ambient light sensor data.
     ///
     /// Returns `nb::Error::WouldBlock` as long as the data is not ready.
+    /// This behavior can be changed with
+    /// [`set_read_mode()`](struct.Apds9960.html#method.set_read_mode).
     /// This clears the data ready flag.
     pub fn read_light(&mut self) -> nb::Result<LightData, Error<E>> {
-        if !self.is_light_data_valid().map_err(nb::Error::Other)? {
-            return Err(nb::Error::WouldBlock);
-        }
+        self.wait_ready(Self::is_light_data_valid)?;
+        self.read_light_data().map_err(nb::Error::Other)
+    }
+
+    /// Read the color / ambient light sensor data together with whether the
+    /// clear photodiode was saturated (CPSAT).
+    ///
+    /// The saturation flag is taken from the same STATUS read that reported the
+    /// data as ready, so saturated samples can be discarded with a single call.
+    ///
+    /// Returns `nb::Error::WouldBlock` as long as the data is not ready.
+    /// This behavior can be changed with
+    /// [`set_read_mode()`](struct.Apds9960.html#method.set_read_mode).
+    /// This clears the data ready flag.
+    pub fn read_light_checked(&mut self) -> nb::Result<(LightData, bool), Error<E>> {
+        let saturated = Cell::new(false);
+        self.wait_ready(|dev| {
+            let status = Status::create(dev.read_register(Register::STATUS)?);
+            saturated.set(status.is(Status::CPSAT, true));
+            Ok(status.is(Status::AVALID, true))
+        })?;
+        let data = self.read_light_data().map_err(nb::Error::Other)?;
+        Ok((data, saturated.get()))
+    }
+
+    fn read_light_data(&mut self) -> Result<LightData, Error<E>> {
         let mut data = [0; 8];
-        self.read_data(Register::CDATAL, &mut data)
-            .map_err(nb::Error::Other)?;
+        self.read_data(Register::CDATAL, &mut data)?;
         Ok(LightData {
             clear: (u16::from(data[1]) << 8) | u16::from(data[0]),
             red: (u16::from(data[3]) << 8) | u16::from(data[2]),
@@ -109,33 +677,41 @@ where
     /// Read the color / ambient light sensor clear channel data.
     ///
     /// Returns `nb::Error::WouldBlock` as long as the data is not ready.
+    /// This behavior can be changed with
+    /// [`set_read_mode()`](struct.Apds9960.html#method.set_read_mode).
     /// This clears the data ready flag.
     pub fn read_light_clear(&mut self) -> nb::Result<u16, Error<E>> {
-        self.read_light_channel(Register::CDATAL)
+        self.read_channel(Channel::Clear)
     }
 
     /// Read the color / ambient light sensor red channel data.
     ///
     /// Returns `nb::Error::WouldBlock` as long as the data is not ready.
+    /// This behavior can be changed with
+    /// [`set_read_mode()`](struct.Apds9960.html#method.set_read_mode).
     /// This clears the data ready flag.
     pub fn read_light_red(&mut self) -> nb::Result<u16, Error<E>> {
-        self.read_light_channel(Register::RDATAL)
+        self.read_channel(Channel::Red)
     }
 
     /// Read the color / ambient light sensor green channel data.
     ///
     /// Returns `nb::Error::WouldBlock` as long as the data is not ready.
+    /// This behavior can be changed with
+    /// [`set_read_mode()`](struct.Apds9960.html#method.set_read_mode).
     /// This clears the data ready flag.
     pub fn read_light_green(&mut self) -> nb::Result<u16, Error<E>> {
-        self.read_light_channel(Register::GDATAL)
+        self.read_channel(Channel::Green)
     }
 
     /// Read the color / ambient light sensor blue channel data.
     ///
     /// Returns `nb::Error::WouldBlock` as long as the data is not ready.
+    /// This behavior can be changed with
+    /// [`set_read_mode()`](struct.Apds9960.html#method.set_read_mode).
     /// This clears the data ready flag.
     pub fn read_light_blue(&mut self) -> nb::Result<u16, Error<E>> {
-        self.read_light_channel(Register::BDATAL)
+        self.read_channel(Channel::Blue)
     }
 
     /// Read whether the color and ambient light sensor data is valid.
@@ -147,13 +723,219 @@ where
         Ok(Status::create(status).is(Status::AVALID, true))
     }
 
-    fn read_light_channel(&mut self, register: u8) -> nb::Result<u16, Error<E>> {
-        if !self.is_light_data_valid().map_err(nb::Error::Other)? {
-            return Err(nb::Error::WouldBlock);
+    /// Read whether the clear photodiode is saturated (CPSAT).
+    ///
+    /// This happens when the clear channel reaches its maximum count for the
+    /// current integration time or the analog front-end saturates.
+    #[allow(clippy::wrong_self_convention)]
+    pub fn is_color_saturated(&mut self) -> Result<bool, Error<E>> {
+        let status = self.read_register(Register::STATUS)?;
+        Ok(Status::create(status).is(Status::CPSAT, true))
+    }
+
+    /// Automatically adjust the color / ambient light gain to the current light conditions.
+    ///
+    /// The clear channel is measured and, if it is close to saturation (above 90% of the
+    /// maximum count for the configured integration time) the gain is stepped down.
+    /// If it is very low (below 10% of the maximum count) the gain is stepped up.
+    /// This is repeated until the reading falls within that window, the gain limits are
+    /// reached or a maximum of 8 iterations has been done.
+    ///
+    /// The delay is used to wait for an integration cycle to complete after each change.
+    /// The color / ambient light engine must be enabled.
+    ///
+    /// Returns the gain that has been finally configured.
+    pub fn auto_adjust_light_gain<D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+    ) -> Result<LightGain, Error<E>> {
+        let atime = self.read_register(Register::ATIME)?;
+        let max_count = u32::from(max_count_for_integration_time(atime));
+        let high = max_count * 9 / 10;
+        let low = max_count / 10;
+        let integration_us = u32::from(atime_to_cycles(atime)) * 2780;
+        let mut gain = self.get_light_gain()?;
+        for _ in 0..AUTO_GAIN_MAX_ITERATIONS {
+            delay.delay_us(integration_us);
+            let clear = match self.read_light_clear() {
+                Ok(clear) => u32::from(clear),
+                Err(nb::Error::WouldBlock) => continue,
+                Err(nb::Error::Other(e)) => return Err(e),
+            };
+            let new_gain = if clear >= high {
+                match gain {
+                    LightGain::X1 => break,
+                    LightGain::X4 => LightGain::X1,
+                    LightGain::X16 => LightGain::X4,
+                    LightGain::X64 => LightGain::X16,
+                }
+            } else if clear <= low {
+                match gain {
+                    LightGain::X1 => LightGain::X4,
+                    LightGain::X4 => LightGain::X16,
+                    LightGain::X16 => LightGain::X64,
+                    LightGain::X64 => break,
+                }
+            } else {
+                break;
+            };
+            self.set_light_gain(new_gain)?;
+            gain = new_gain;
         }
-        let mut data = [0; 2];
-        self.read_data(register, &mut data)
-            .map_err(nb::Error::Other)?;
-        Ok((u16::from(data[1]) << 8) | u16::from(data[0]))
+        Ok(gain)
     }
+
+    /// Automatically adjust the color / ambient light integration time to the current
+    /// light conditions.
+    ///
+    /// The clear channel is measured and, if it is close to saturation (above 90% of the
+    /// maximum count for the configured integration time) the number of integration
+    /// cycles is halved. If it is very low (below 10% of the maximum count) the number
+    /// of cycles is doubled (up to 256). This is repeated until the reading falls within
+    /// that window, the integration time limits are reached or a maximum of 8 iterations
+    /// has been done.
+    ///
+    /// Compared to [`auto_adjust_light_gain()`](struct.Apds9960.html#method.auto_adjust_light_gain)
+    /// this increases the dynamic range at the cost of a lower measurement rate.
+    /// The delay is used to wait for an integration cycle to complete after each change.
+    /// The data of the cycle still running with the previous integration time is
+    /// discarded after each change.
+    /// The color / ambient light engine must be enabled.
+    ///
+    /// Returns the ATIME value that has been finally configured.
+    pub fn auto_adjust_integration_time<D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+    ) -> Result<u8, Error<E>> {
+        let mut atime = self.read_register(Register::ATIME)?;
+        for _ in 0..AUTO_INTEGRATION_MAX_ITERATIONS {
+            let cycles = atime_to_cycles(atime);
+            delay.delay_us(u32::from(cycles) * 2780);
+            let clear = match self.read_light_clear() {
+                Ok(clear) => u32::from(clear),
+                Err(nb::Error::WouldBlock) => continue,
+                Err(nb::Error::Other(e)) => return Err(e),
+            };
+            let max_count = u32::from(max_count_for_integration_time(atime));
+            let new_cycles = if clear >= max_count * 9 / 10 {
+                if cycles == 1 {
+                    break;
+                }
+                cycles / 2
+            } else if clear <= max_count / 10 {
+                if cycles == 256 {
+                    break;
+                }
+                core::cmp::min(cycles * 2, 256)
+            } else {
+                break;
+            };
+            let previous_atime = atime;
+            atime = atime_from_cycles(new_cycles);
+            self.set_light_integration_time(atime)?;
+            self.discard_stale_light_data(delay, previous_atime)?;
+        }
+        Ok(atime)
+    }
+
+    /// Take a quick clear channel (ambient light) sample.
+    ///
+    /// The integration time is temporarily shortened to 10 cycles (27.8ms), one clear
+    /// channel reading is taken and then the previous integration time is restored.
+    /// Since the cycle in progress when shortening the integration time still uses the
+    /// previous one, that cycle is waited out and its data is discarded first, so this
+    /// takes up to the previous integration time plus one short cycle.
+    /// The returned value is scaled to be comparable with readings taken with the
+    /// previous integration time, at the cost of a lower resolution.
+    ///
+    /// The previous integration time is restored even if an error occurs.
+    /// The color / ambient light engine must be enabled.
+    pub fn quick_ambient<D: DelayNs>(&mut self, delay: &mut D) -> Result<u16, Error<E>> {
+        let atime = self.read_register(Register::ATIME)?;
+        self.write_register(Register::ATIME, QUICK_AMBIENT_ATIME)?;
+        let result = self
+            .discard_stale_light_data(delay, atime)
+            .and_then(|_| self.read_quick_clear(delay));
+        self.write_register(Register::ATIME, atime)?;
+        let clear = u32::from(result?);
+        let scaled = clear * u32::from(atime_to_cycles(atime))
+            / u32::from(atime_to_cycles(QUICK_AMBIENT_ATIME));
+        Ok(core::cmp::min(scaled, u32::from(max_count_for_integration_time(atime))) as u16)
+    }
+
+    fn read_quick_clear<D: DelayNs>(&mut self, delay: &mut D) -> Result<u16, Error<E>> {
+        let integration_us = u32::from(atime_to_cycles(QUICK_AMBIENT_ATIME)) * 2780;
+        for _ in 0..QUICK_AMBIENT_MAX_POLLS {
+            delay.delay_us(integration_us);
+            match self.read_light_clear() {
+                Ok(clear) => return Ok(clear),
+                Err(nb::Error::WouldBlock) => continue,
+                Err(nb::Error::Other(e)) => return Err(e),
+            }
+        }
+        Err(Error::Timeout)
+    }
+
+    /// Discard the color / ambient light data measured with the previous integration time.
+    ///
+    /// After changing ATIME, the cycle in progress still uses the previous integration
+    /// time and its result sets AVALID like any other. This waits for that cycle to
+    /// finish and reads (and thereby discards) the data if it is valid, so that the
+    /// next valid data comes from a cycle with the new integration time.
+    fn discard_stale_light_data<D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+        previous_atime: u8,
+    ) -> Result<(), Error<E>> {
+        delay.delay_us(u32::from(atime_to_cycles(previous_atime)) * 2780);
+        if self.is_light_data_valid()? {
+            self.read_double_register(Register::CDATAL)?;
+        }
+        Ok(())
+    }
+
+    /// Read the data of a color / ambient light sensor channel.
+    ///
+    /// Returns `nb::Error::WouldBlock` as long as the data is not ready.
+    /// This behavior can be changed with
+    /// [`set_read_mode()`](struct.Apds9960.html#method.set_read_mode).
+    /// This clears the data ready flag.
+    pub fn read_channel(&mut self, channel: Channel) -> nb::Result<u16, Error<E>> {
+        self.wait_ready(Self::is_light_data_valid)?;
+        self.read_double_register(channel.register())
+            .map_err(nb::Error::Other)
+    }
+}
+
+/// Maximum (full-scale) count of the color / ambient light channels for an ATIME value.
+///
+/// Per the datasheet this is `1025 * (256 - ATIME)`, clamped to 65535. For example,
+/// ATIME `0xFF` (1 cycle, 2.78ms) gives 1025 and ATIME `0` (256 cycles, 712ms) gives 65535.
+pub fn max_count_for_integration_time(atime: u8) -> u16 {
+    let cycles = u32::from(atime_to_cycles(atime));
+    core::cmp::min(1025 * cycles, 0xFFFF) as u16
+}
+
+fn light_gain_factor(gain: LightGain) -> f32 {
+    match gain {
+        LightGain::X1 => 1.0,
+        LightGain::X4 => 4.0,
+        LightGain::X16 => 16.0,
+        LightGain::X64 => 64.0,
+    }
+}
+
+/// Convert a number of integration cycles into the corresponding ATIME register value.
+///
+/// One cycle is 2.78ms. The number of cycles is clamped to the supported range of
+/// 1 to 256, so 1 cycle gives `0xFF` and 256 cycles give `0`.
+pub fn atime_from_cycles(cycles: u16) -> u8 {
+    (256 - cycles.clamp(1, 256)) as u8
+}
+
+/// Convert an ATIME register value into the corresponding number of integration cycles.
+///
+/// This is the inverse of [`atime_from_cycles()`]: `0xFF` gives 1 cycle and `0` gives 256.
+pub fn atime_to_cycles(atime: u8) -> u16 {
+    256 - u16::from(atime)
 }
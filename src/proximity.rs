@@ -1,9 +1,258 @@
-use hal::i2c;
+use hal::{delay::DelayNs, i2c};
 use {
-    register::{Config2, Config3, Enable, Pers, Status},
-    Apds9960, BitFlags, Error, Register,
+    defaults,
+    register::{Config1, Config2, Config3, Enable, Pers, Status},
+    Apds9960, BitFlags, Error, LedDrive, ProximityGain, Register,
 };
 
+/// Number of readings taken by `auto_tune_proximity_thresholds()`.
+const AUTO_TUNE_SAMPLES: usize = 8;
+
+/// Rough indicator of the maximum distance at which objects can be detected.
+///
+/// See [`estimated_max_range()`](struct.Apds9960.html#method.estimated_max_range).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RangeClass {
+    /// Only nearby objects will be detected.
+    Short,
+    /// Objects at a moderate distance will be detected.
+    Medium,
+    /// Objects far away will be detected.
+    Long,
+}
+
+/// Moving-average filter for proximity readings.
+///
+/// Keeps the last `N` samples and returns their average.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProximityFilter<const N: usize> {
+    samples: [u8; N],
+    index: usize,
+    len: usize,
+}
+
+impl<const N: usize> Default for ProximityFilter<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> ProximityFilter<N> {
+    /// Create a new empty filter.
+    pub fn new() -> Self {
+        ProximityFilter {
+            samples: [0; N],
+            index: 0,
+            len: 0,
+        }
+    }
+
+    /// Add a sample and return the average of the stored samples.
+    ///
+    /// Before `N` samples have been added, the average of the samples added
+    /// so far is returned. With `N == 0` the sample is returned unfiltered.
+    pub fn push(&mut self, sample: u8) -> u8 {
+        if N == 0 {
+            return sample;
+        }
+        self.samples[self.index] = sample;
+        self.index = (self.index + 1) % N;
+        if self.len < N {
+            self.len += 1;
+        }
+        let sum: u32 = self.samples[..self.len].iter().map(|&s| u32::from(s)).sum();
+        (sum / self.len as u32) as u8
+    }
+
+    /// Read the proximity from the sensor, add it to the filter and return the average.
+    ///
+    /// Returns `nb::Error::WouldBlock` as long as the data is not ready.
+    pub fn read_filtered<I2C, E>(&mut self, sensor: &mut Apds9960<I2C>) -> nb::Result<u8, Error<E>>
+    where
+        I2C: i2c::I2c<Error = E>,
+    {
+        let sample = sensor.read_proximity()?;
+        Ok(self.push(sample))
+    }
+
+    /// Discard all stored samples.
+    pub fn reset(&mut self) {
+        self.index = 0;
+        self.len = 0;
+    }
+}
+
+/// Slowly-adapting proximity baseline tracker.
+///
+/// Follows the proximity reading when no object is present (for example, drift due to
+/// temperature or dust on the cover glass) so that presence can be detected as a
+/// deviation from this baseline instead of against a fixed threshold.
+///
+/// The baseline is an exponential moving average of the readings. Readings more than
+/// `margin` above the baseline are considered to be caused by an object and are not
+/// used for adaptation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BaselineTracker {
+    /// Baseline in 8.8 fixed point.
+    baseline: u16,
+    shift: u8,
+    margin: u8,
+    initialized: bool,
+}
+
+impl BaselineTracker {
+    /// Create a new tracker.
+    ///
+    /// * `shift`: Adaptation speed. Each reading moves the baseline by
+    ///   `1 / 2^shift` of the difference (0-8, higher is slower).
+    /// * `margin`: Readings more than this above the baseline do not adapt it.
+    ///
+    /// The first reading initializes the baseline.
+    pub fn new(shift: u8, margin: u8) -> Self {
+        BaselineTracker {
+            baseline: 0,
+            shift: core::cmp::min(shift, 8),
+            margin,
+            initialized: false,
+        }
+    }
+
+    /// Add a reading and return the baseline-subtracted value.
+    pub fn update(&mut self, sample: u8) -> u8 {
+        let target = u16::from(sample) << 8;
+        if !self.initialized {
+            self.baseline = target;
+            self.initialized = true;
+        } else if sample <= self.baseline().saturating_add(self.margin) {
+            if target > self.baseline {
+                self.baseline += (target - self.baseline) >> self.shift;
+            } else {
+                self.baseline -= (self.baseline - target) >> self.shift;
+            }
+        }
+        self.delta(sample)
+    }
+
+    /// Current baseline value.
+    pub fn baseline(&self) -> u8 {
+        (self.baseline >> 8) as u8
+    }
+
+    /// Reading minus baseline, saturating at 0.
+    pub fn delta(&self, sample: u8) -> u8 {
+        sample.saturating_sub(self.baseline())
+    }
+}
+
+/// Calibration curve mapping raw proximity readings to distances.
+///
+/// The relation between the proximity reading and the distance depends on the
+/// optical setup and the reflectivity of the object, so it must be measured.
+/// The curve is made of `N` anchor points `(raw, cm)` which are linearly
+/// interpolated. Readings outside of the anchor points are extrapolated from the
+/// closest segment.
+///
+/// See [`proximity_to_cm()`](fn.proximity_to_cm.html).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProximityCurve<const N: usize> {
+    points: [(u8, f32); N],
+}
+
+impl<const N: usize> ProximityCurve<N> {
+    /// Create a curve from `(raw, cm)` anchor points.
+    ///
+    /// The points do not need to be sorted.
+    pub fn new(mut points: [(u8, f32); N]) -> Self {
+        points.sort_unstable_by_key(|p| p.0);
+        ProximityCurve { points }
+    }
+}
+
+/// Convert a raw proximity reading to an approximate distance in centimeters.
+///
+/// With no anchor points `0.0` is returned and with a single one its distance.
+pub fn proximity_to_cm<const N: usize>(raw: u8, cal: &ProximityCurve<N>) -> f32 {
+    let points = &cal.points;
+    match N {
+        0 => return 0.0,
+        1 => return points[0].1,
+        _ => (),
+    }
+    let segment = points[1..N - 1]
+        .iter()
+        .position(|p| raw < p.0)
+        .unwrap_or(N - 2);
+    let (raw0, cm0) = points[segment];
+    let (raw1, cm1) = points[segment + 1];
+    if raw0 == raw1 {
+        return cm0;
+    }
+    let t = (f32::from(raw) - f32::from(raw0)) / (f32::from(raw1) - f32::from(raw0));
+    cm0 + t * (cm1 - cm0)
+}
+
+/// Proximity engine settings applied together with
+/// [`apply_proximity_config()`](struct.Apds9960.html#method.apply_proximity_config).
+///
+/// The default values are the ones written by `init()`. Individual settings can be
+/// changed with the struct update syntax, e.g.
+/// `ProximityConfig { gain: ProximityGain::X4, ..ProximityConfig::low_power() }`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProximityConfig {
+    /// Proximity gain. (default: 4x)
+    pub gain: ProximityGain,
+    /// LED drive current. This is shared with the color / ambient light engine. (default: 100mA)
+    pub led_drive: LedDrive,
+    /// Proximity pulse count (0-63, actual pulses = value + 1). (default: 7)
+    pub pulse_count: u8,
+    /// Proximity pulse length (0=4us, 1=8us, 2=16us, 3=32us). (default: 2)
+    pub pulse_length: u8,
+    /// Wait between cycles (WEN). (default: false)
+    pub wait: bool,
+    /// Wait time as 2's complement of the number of cycles, see
+    /// [`set_wait_time()`](struct.Apds9960.html#method.set_wait_time). (default: 246)
+    pub wait_time: u8,
+    /// Multiply the wait time by 12 (WLONG). (default: false)
+    pub wait_long: bool,
+}
+
+impl Default for ProximityConfig {
+    fn default() -> Self {
+        ProximityConfig {
+            gain: ProximityGain::X4,
+            led_drive: LedDrive::Ma100,
+            pulse_count: 7,
+            pulse_length: 2,
+            wait: false,
+            wait_time: defaults::WTIME,
+            wait_long: false,
+        }
+    }
+}
+
+impl ProximityConfig {
+    /// Settings for battery-powered presence detection.
+    ///
+    /// The LED is driven with 25mA for 4 pulses of 8us instead of 100mA for
+    /// 8 pulses of 16us, which reduces the LED energy per reading about 16 times.
+    /// The 8x gain partially compensates for the weaker signal.
+    /// Between readings the device spends about 1.2s in the low-power wait state
+    /// (36 cycles with long wait).
+    ///
+    /// The tradeoff is a shorter detection range and a reaction time of over a second.
+    pub fn low_power() -> Self {
+        ProximityConfig {
+            gain: ProximityGain::X8,
+            led_drive: LedDrive::Ma25,
+            pulse_count: 3,
+            pulse_length: 1,
+            wait: true,
+            wait_time: 0xDC,
+            wait_long: true,
+        }
+    }
+}
+
 /// Proximity sensor implementation with comprehensive register access.
 impl<I2C, E> Apds9960<I2C>
 where
@@ -32,6 +281,19 @@ where
         self.enable_proximity()
     }
 
+    /// Apply the proximity engine settings.
+    ///
+    /// Returns `Error::InvalidParameter` if the pulse count or length is out of range.
+    /// In this case nothing is written.
+    pub fn apply_proximity_config(&mut self, config: &ProximityConfig) -> Result<(), Error<E>> {
+        self.set_proximity_pulse(config.pulse_count, config.pulse_length)?;
+        self.set_proximity_gain(config.gain)?;
+        self.set_led_drive(config.led_drive)?;
+        self.set_wait_time(config.wait_time)?;
+        self.set_flag_config1(Config1::WLONG, config.wait_long)?;
+        self.set_flag_enable(Enable::WEN, config.wait)
+    }
+
     /// Disable proximity sensor
     pub fn disable_proximity_sensor(&mut self) -> Result<(), Error<E>> {
         self.disable_proximity_interrupts()?;
@@ -78,24 +340,60 @@ where
         self.read_register(Register::PIHT)
     }
 
+    /// Set the proximity interrupt low and high thresholds.
+    ///
+    /// Returns `Error::InvalidInputData` if `low` is greater than `high`.
+    pub fn set_proximity_thresholds(&mut self, low: u8, high: u8) -> Result<(), Error<E>> {
+        if low > high {
+            return Err(Error::InvalidInputData);
+        }
+        self.set_proximity_low_threshold(low)?;
+        self.set_proximity_high_threshold(high)
+    }
+
+    /// Get the proximity interrupt low and high thresholds.
+    ///
+    /// Returns `(low, high)`.
+    pub fn get_proximity_thresholds(&mut self) -> Result<(u8, u8), Error<E>> {
+        let low = self.get_proximity_low_threshold()?;
+        let high = self.get_proximity_high_threshold()?;
+        Ok((low, high))
+    }
+
     /// Set the proximity up/right photodiode offset.
+    ///
+    /// The magnitude is limited to 127, so `-128` is written as `-127`.
     pub fn set_proximity_up_right_offset(&mut self, offset: i8) -> Result<(), Error<E>> {
-        self.write_register(Register::POFFSET_UR, offset as u8)
+        self.write_register(Register::POFFSET_UR, encode_offset(offset))
     }
 
     /// Set the proximity down/left photodiode offset.
+    ///
+    /// The magnitude is limited to 127, so `-128` is written as `-127`.
     pub fn set_proximity_down_left_offset(&mut self, offset: i8) -> Result<(), Error<E>> {
-        self.write_register(Register::POFFSET_DL, offset as u8)
+        self.write_register(Register::POFFSET_DL, encode_offset(offset))
     }
 
     /// Set the proximity up/right and down/left photodiode offset.
+    ///
+    /// Both registers are written in a single operation.
     pub fn set_proximity_offsets(
         &mut self,
         offset_up_right: i8,
         offset_down_left: i8,
     ) -> Result<(), Error<E>> {
-        self.set_proximity_up_right_offset(offset_up_right)?;
-        self.set_proximity_down_left_offset(offset_down_left)
+        let value = (u16::from(encode_offset(offset_down_left)) << 8)
+            | u16::from(encode_offset(offset_up_right));
+        self.write_double_register(Register::POFFSET_UR, value)
+    }
+
+    /// Read the proximity up/right and down/left photodiode offsets.
+    ///
+    /// Returns `(up_right, down_left)`.
+    pub fn get_proximity_offsets(&mut self) -> Result<(i8, i8), Error<E>> {
+        let mut data = [0; 2];
+        self.read_data(Register::POFFSET_UR, &mut data)?;
+        Ok((decode_offset(data[0]), decode_offset(data[1])))
     }
 
     /// Set proximity interrupt persistence.
@@ -104,9 +402,14 @@ where
     /// are required before triggering a proximity interrupt.
     ///
     /// * `cycles`: Number of consecutive cycles (0-15)
+    ///
+    /// Returns `Error::InvalidParameter` if `cycles` is greater than 15.
     pub fn set_proximity_interrupt_persistence(&mut self, cycles: u8) -> Result<(), Error<E>> {
+        if cycles > 0x0F {
+            return Err(Error::InvalidParameter);
+        }
         let mut pers = self.read_register(Register::PERS)?;
-        pers = (pers & !Pers::PPERS_MASK) | ((cycles & 0x0F) << Pers::PPERS_SHIFT);
+        pers = (pers & !Pers::PPERS_MASK) | (cycles << Pers::PPERS_SHIFT);
         self.write_register(Register::PERS, pers)
     }
 
@@ -136,10 +439,17 @@ where
     ///
     /// Each bit masks a photodiode: bit 0=Right, 1=Left, 2=Down, 3=Up
     /// Setting a bit to 1 disables that photodiode.
+    ///
+    /// Returns `Error::InvalidParameter` if `mask` is greater than `0x0F`.
     pub fn set_proximity_photodiode_mask(&mut self, mask: u8) -> Result<(), Error<E>> {
+        if mask > 0x0F {
+            return Err(Error::InvalidParameter);
+        }
         let mut config3 = self.read_register(Register::CONFIG3)?;
-        config3 = (config3 & 0xF0) | (mask & 0x0F);
-        self.write_register(Register::CONFIG3, config3)
+        config3 = (config3 & 0xF0) | mask;
+        self.write_register(Register::CONFIG3, config3)?;
+        self.config3 = Config3::create(config3);
+        Ok(())
     }
 
     /// Get proximity photodiode mask.
@@ -148,22 +458,154 @@ where
         Ok(config3 & 0x0F)
     }
 
+    /// Estimate how far the current proximity configuration can detect objects.
+    ///
+    /// The proximity gain, LED drive strength, LED boost and pulse count and length
+    /// are combined into an approximate emitted/received signal strength and mapped to
+    /// a `RangeClass`.
+    ///
+    /// This is only an estimate. The actual range depends heavily on the object
+    /// reflectivity, the ambient light and the optical design (cover glass, crosstalk).
+    pub fn estimated_max_range(&mut self) -> Result<RangeClass, Error<E>> {
+        let control = self.read_register(Register::CONTROL)?;
+        let config2 = self.read_register(Register::CONFIG2)?;
+        let ppulse = self.read_register(Register::PPULSE)?;
+
+        // Score in half-steps of signal strength (each full step doubles it).
+        let gain = (control >> 2) & 0x03;
+        let drive = 3 - ((control >> 6) & 0x03);
+        let boost = (config2 >> 4) & 0x03;
+        let length = (ppulse >> 6) & 0x03;
+        let pulses = u32::from(ppulse & 0x3F) + 1;
+        let pulses_log2 = 31 - pulses.leading_zeros();
+        let score = 2 * u32::from(gain + drive + length) + u32::from(boost) + 2 * pulses_log2;
+
+        Ok(if score >= 24 {
+            RangeClass::Long
+        } else if score >= 14 {
+            RangeClass::Medium
+        } else {
+            RangeClass::Short
+        })
+    }
+
     /// Clear proximity interrupt.
     pub fn clear_proximity_interrupt(&mut self) -> Result<(), Error<E>> {
         self.touch_register(Register::PICLEAR)
     }
 
+    /// Read the proximity sensor data and clear the proximity interrupt afterwards.
+    ///
+    /// This is meant for interrupt handlers: the data valid flag is not checked
+    /// since the interrupt already signals new data.
+    pub fn read_and_clear_proximity_interrupt(&mut self) -> Result<u8, Error<E>> {
+        let proximity = self.read_register(Register::PDATA)?;
+        self.clear_proximity_interrupt()?;
+        Ok(proximity)
+    }
+
     /// Read the proximity sensor data.
     ///
     /// Returns `nb::Error::WouldBlock` as long as the data is not ready.
+    /// This behavior can be changed with
+    /// [`set_read_mode()`](struct.Apds9960.html#method.set_read_mode).
+    ///
+    /// This takes two transactions: STATUS is read and then PDATA. They are not read
+    /// in a single burst because the color data registers lie in between and reading
+    /// them clears the color / ambient light data valid flag, which would make pending
+    /// color data be lost when both engines are enabled.
     pub fn read_proximity(&mut self) -> nb::Result<u8, Error<E>> {
-        if !self.is_proximity_data_valid().map_err(nb::Error::Other)? {
-            return Err(nb::Error::WouldBlock);
-        }
+        self.wait_ready(Self::is_proximity_data_valid)?;
         self.read_register(Register::PDATA)
             .map_err(nb::Error::Other)
     }
 
+    /// Read the proximity sensor data without checking whether it is valid.
+    ///
+    /// This is intended for free-running setups where the timing is managed by
+    /// the caller. The value may be stale, i.e. from a previous proximity cycle,
+    /// or zero if no cycle has completed yet.
+    pub fn read_proximity_raw(&mut self) -> Result<u8, Error<E>> {
+        self.read_register(Register::PDATA)
+    }
+
+    /// Read the proximity sensor data, waiting until it is ready.
+    ///
+    /// The data valid flag is polled every 5ms, independently of the configured
+    /// [read mode](struct.Apds9960.html#method.set_read_mode).
+    /// Returns `Error::Timeout` if the data is not ready after about 1s.
+    pub fn read_proximity_blocking<D: DelayNs>(&mut self, delay: &mut D) -> Result<u8, Error<E>> {
+        self.poll_with_delay(delay, |dev| {
+            if !dev.is_proximity_data_valid().map_err(nb::Error::Other)? {
+                return Err(nb::Error::WouldBlock);
+            }
+            dev.read_register(Register::PDATA).map_err(nb::Error::Other)
+        })
+    }
+
+    /// Fill `out` with consecutive proximity readings, waiting for valid data
+    /// before each of them.
+    ///
+    /// Each reading is taken as in
+    /// [`read_proximity_blocking()`](struct.Apds9960.html#method.read_proximity_blocking).
+    /// If the data is not ready in time, collection stops early.
+    /// Returns the number of readings stored at the beginning of `out`.
+    pub fn read_proximity_samples<D: DelayNs>(
+        &mut self,
+        out: &mut [u8],
+        delay: &mut D,
+    ) -> Result<usize, Error<E>> {
+        for (count, sample) in out.iter_mut().enumerate() {
+            match self.read_proximity_blocking(delay) {
+                Ok(value) => *sample = value,
+                Err(Error::Timeout) => return Ok(count),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(out.len())
+    }
+
+    /// Set the proximity interrupt thresholds around the current noise floor.
+    ///
+    /// There must be no object in front of the sensor and the proximity engine
+    /// must be enabled. 8 proximity readings are taken as in
+    /// [`read_proximity_samples()`](struct.Apds9960.html#method.read_proximity_samples)
+    /// and their average is used as noise floor, which includes the crosstalk of
+    /// the particular assembly. The low and high thresholds are then set to the
+    /// noise floor minus and plus `margin` (saturating at 0 and 255).
+    ///
+    /// Returns the configured thresholds as `(low, high)`, or `Error::Timeout`
+    /// if no reading could be taken.
+    pub fn auto_tune_proximity_thresholds<D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+        margin: u8,
+    ) -> Result<(u8, u8), Error<E>> {
+        let mut samples = [0; AUTO_TUNE_SAMPLES];
+        let count = self.read_proximity_samples(&mut samples, delay)?;
+        if count == 0 {
+            return Err(Error::Timeout);
+        }
+        let sum: u32 = samples[..count].iter().map(|&s| u32::from(s)).sum();
+        let floor = (sum / count as u32) as u8;
+        let low = floor.saturating_sub(margin);
+        let high = floor.saturating_add(margin);
+        self.set_proximity_thresholds(low, high)?;
+        Ok((low, high))
+    }
+
+    /// Read the proximity sensor data, update the baseline tracker with it and
+    /// return the baseline-subtracted value.
+    ///
+    /// Returns `nb::Error::WouldBlock` as long as the data is not ready.
+    pub fn proximity_vs_baseline(
+        &mut self,
+        tracker: &mut BaselineTracker,
+    ) -> nb::Result<u8, Error<E>> {
+        let sample = self.read_proximity()?;
+        Ok(tracker.update(sample))
+    }
+
     /// Read whether the proximity sensor data is valid.
     ///
     /// This is checked internally in `read_proximity()` as well.
@@ -172,4 +614,55 @@ where
         let status = self.read_register(Register::STATUS)?;
         Ok(Status::create(status).is(Status::PVALID, true))
     }
+
+    /// Read whether the proximity measurement is saturated (PGSAT).
+    ///
+    /// This happens when there is too much ambient light or the reflected
+    /// signal is too strong for the current gain and LED settings.
+    #[allow(clippy::wrong_self_convention)]
+    pub fn is_proximity_saturated(&mut self) -> Result<bool, Error<E>> {
+        let status = self.read_register(Register::STATUS)?;
+        Ok(Status::create(status).is(Status::PGSAT, true))
+    }
+
+    /// Read whether a proximity saturation interrupt occurred.
+    ///
+    /// This is the case if the proximity measurement is saturated (PGSAT) and
+    /// proximity saturation interrupts are enabled (see
+    /// [`enable_proximity_saturation_interrupts()`](struct.Apds9960.html#method.enable_proximity_saturation_interrupts)).
+    /// An interrupt handler can use this to distinguish a saturation event from a
+    /// proximity threshold interrupt. Saturation is cleared together with the
+    /// proximity interrupt with
+    /// [`clear_proximity_interrupt()`](struct.Apds9960.html#method.clear_proximity_interrupt).
+    #[allow(clippy::wrong_self_convention)]
+    pub fn is_proximity_saturation_interrupt(&mut self) -> Result<bool, Error<E>> {
+        let status = self.read_register(Register::STATUS)?;
+        Ok(self.config2.is(Config2::PSIEN, true) && Status::create(status).is(Status::PGSAT, true))
+    }
+}
+
+/// Encode an offset for the offset registers (POFFSET_UR/DL, GOFFSET_U/D/L/R).
+///
+/// The offsets are stored in sign-magnitude format: bit 7 is the sign and
+/// bits 6:0 are the magnitude. The magnitude is limited to 127.
+pub(crate) fn encode_offset(offset: i8) -> u8 {
+    let magnitude = core::cmp::min(offset.unsigned_abs(), 0x7F);
+    if offset < 0 {
+        0x80 | magnitude
+    } else {
+        magnitude
+    }
+}
+
+/// Decode an offset register value (POFFSET_UR/DL, GOFFSET_U/D/L/R).
+///
+/// The offsets are stored in sign-magnitude format: bit 7 is the sign and
+/// bits 6:0 are the magnitude.
+pub(crate) fn decode_offset(value: u8) -> i8 {
+    let magnitude = (value & 0x7F) as i8;
+    if value & 0x80 != 0 {
+        -magnitude
+    } else {
+        magnitude
+    }
 }
@@ -0,0 +1,53 @@
+//! Raw register field access.
+//!
+//! This is only available with the `unstable-raw` feature enabled and its API
+//! may change in any release.
+
+use hal::i2c;
+use {Apds9960, Error};
+
+/// Raw register field access.
+impl<I2C, E> Apds9960<I2C>
+where
+    I2C: i2c::I2c<Error = E>,
+{
+    /// Read a field of an arbitrary register.
+    ///
+    /// The register value is masked with `mask` and then shifted right by `shift`,
+    /// so that `mask` describes the position of the field inside the register.
+    /// For example, the proximity gain field in the CONTROL register (`0x8F`) is
+    /// read with `field(0x8F, 0b0000_1100, 2)`.
+    ///
+    /// Returns `Error::InvalidParameter` if `shift` is greater than 7 or if `mask`
+    /// has no bits set at or above `shift`. In this case nothing is read.
+    pub fn field(&mut self, reg: u8, mask: u8, shift: u8) -> Result<u8, Error<E>> {
+        check_field(mask, shift)?;
+        let value = self.read_register(reg)?;
+        Ok((value & mask) >> shift)
+    }
+
+    /// Set a field of an arbitrary register.
+    ///
+    /// The register is read, the bits selected by `mask` are replaced by
+    /// `value` shifted left by `shift`, and the result is written back.
+    /// Bits of `value` that fall outside of `mask` are ignored.
+    ///
+    /// The copies of the registers cached by the driver (ENABLE, PERS, CONFIG1,
+    /// CONFIG2, CONFIG3, GCONF1 and GCONF4) are updated as well.
+    ///
+    /// Returns `Error::InvalidParameter` if `shift` is greater than 7 or if `mask`
+    /// has no bits set at or above `shift`. In this case nothing is written.
+    pub fn set_field(&mut self, reg: u8, mask: u8, shift: u8, value: u8) -> Result<(), Error<E>> {
+        check_field(mask, shift)?;
+        let current = self.read_register(reg)?;
+        let new = (current & !mask) | ((value << shift) & mask);
+        self.write_register(reg, new)
+    }
+}
+
+fn check_field<E>(mask: u8, shift: u8) -> Result<(), Error<E>> {
+    if shift > 7 || mask >> shift == 0 {
+        return Err(Error::InvalidParameter);
+    }
+    Ok(())
+}
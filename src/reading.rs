@@ -1,5 +1,11 @@
-use hal::i2c;
-use {Apds9960, Error, Register, DEV_ADDR};
+use hal::{delay::DelayNs, i2c};
+use {config::is_valid_register_block, Apds9960, Error, ReadMode, Register, DEV_ADDR};
+
+/// Time between polls in `poll_with_delay()`.
+const POLL_INTERVAL_MS: u32 = 5;
+
+/// Maximum number of polls in `poll_with_delay()`.
+const POLL_MAX_ATTEMPTS: u32 = 200;
 
 impl<I2C, E> Apds9960<I2C>
 where
@@ -12,15 +18,89 @@ where
         self.read_register(Register::ID)
     }
 
+    /// Read a block of consecutive registers in a single I2C transaction.
+    ///
+    /// `out[0]` is read from `start`, `out[1]` from `start + 1` and so on, relying on
+    /// the register address auto-increment of the device. This is useful to dump
+    /// several registers efficiently, e.g. the whole configuration.
+    ///
+    /// The address auto-increments through the whole `0x80`-`0xFF` range. Reading
+    /// the color / ambient light data (`0x94`-`0x9B`) as a block guarantees that all
+    /// channels belong to the same cycle. A read covering the gesture FIFO
+    /// (`0xFC`-`0xFF`) pops a dataset from it.
+    /// To read the whole gesture FIFO see [`read_gesture_fifo_all()`](struct.Apds9960.html#method.read_gesture_fifo_all).
+    ///
+    /// Returns `Error::InvalidInputData` if `out` is empty or if the block does not lie
+    /// within `0x80`-`0xFF`. In this case nothing is read.
+    pub fn read_registers(&mut self, start: u8, out: &mut [u8]) -> Result<(), Error<E>> {
+        if !is_valid_register_block(start, out.len()) {
+            return Err(Error::InvalidInputData);
+        }
+        self.read_data(start, out)
+    }
+
+    /// Poll `is_ready` according to the configured read mode.
+    pub(crate) fn wait_ready<F>(&mut self, is_ready: F) -> nb::Result<(), Error<E>>
+    where
+        F: Fn(&mut Self) -> Result<bool, Error<E>>,
+    {
+        let mut polls = 0;
+        loop {
+            if is_ready(self).map_err(nb::Error::Other)? {
+                return Ok(());
+            }
+            match self.read_mode {
+                ReadMode::NonBlocking => return Err(nb::Error::WouldBlock),
+                ReadMode::Blocking => (),
+                ReadMode::Timeout(max_polls) => {
+                    polls += 1;
+                    if polls >= max_polls {
+                        return Err(nb::Error::Other(Error::Timeout));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Call `read` until it does not block anymore, waiting between attempts.
+    ///
+    /// Returns `Error::Timeout` after 200 attempts (about 1s).
+    pub(crate) fn poll_with_delay<T, D, F>(
+        &mut self,
+        delay: &mut D,
+        mut read: F,
+    ) -> Result<T, Error<E>>
+    where
+        D: DelayNs,
+        F: FnMut(&mut Self) -> nb::Result<T, Error<E>>,
+    {
+        for _ in 0..POLL_MAX_ATTEMPTS {
+            match read(self) {
+                Ok(value) => return Ok(value),
+                Err(nb::Error::WouldBlock) => delay.delay_ms(POLL_INTERVAL_MS),
+                Err(nb::Error::Other(e)) => return Err(e),
+            }
+        }
+        Err(Error::Timeout)
+    }
+
     pub(crate) fn read_register(&mut self, register: u8) -> Result<u8, Error<E>> {
         let mut data = [0];
         self.read_data(register, &mut data)?;
         Ok(data[0])
     }
 
+    pub(crate) fn read_double_register(&mut self, start_register: u8) -> Result<u16, Error<E>> {
+        let mut data = [0; 2];
+        self.read_data(start_register, &mut data)?;
+        Ok((u16::from(data[1]) << 8) | u16::from(data[0]))
+    }
+
     pub(crate) fn read_data(&mut self, register: u8, data: &mut [u8]) -> Result<(), Error<E>> {
         self.i2c
             .write_read(DEV_ADDR, &[register], data)
-            .map_err(Error::I2C)
+            .map_err(Error::I2C)?;
+        log_trace!("read register 0x{:02X}: {:02X?}", register, data);
+        Ok(())
     }
 }
@@ -0,0 +1,143 @@
+extern crate apds9960;
+use apds9960::{CalibrationBlob, CalibrationStep, LightData};
+extern crate embedded_hal_mock as hal;
+use hal::eh1::{delay::NoopDelay, i2c::Transaction as I2cTrans};
+mod common;
+use common::{destroy, new, BitFlags, Register, DEV_ADDR};
+
+fn proximity_trans() -> Vec<I2cTrans> {
+    vec![
+        I2cTrans::write(DEV_ADDR, vec![Register::POFFSET_UR, 0, 0]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::STATUS], vec![0]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::STATUS], vec![BitFlags::PVALID]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::PDATA], vec![12]),
+        I2cTrans::write(DEV_ADDR, vec![Register::POFFSET_UR, 12, 12]),
+    ]
+}
+
+fn gesture_offsets_trans(u: u8, d: u8, l: u8, r: u8) -> Vec<I2cTrans> {
+    vec![
+        I2cTrans::write(DEV_ADDR, vec![Register::GOFFSET_U, u]),
+        I2cTrans::write(DEV_ADDR, vec![Register::GOFFSET_D, d]),
+        I2cTrans::write(DEV_ADDR, vec![Register::GOFFSET_L, l]),
+        I2cTrans::write(DEV_ADDR, vec![Register::GOFFSET_R, r]),
+    ]
+}
+
+fn gesture_trans() -> Vec<I2cTrans> {
+    let mut trans = gesture_offsets_trans(0, 0, 0, 0);
+    trans.extend_from_slice(&[
+        I2cTrans::write(DEV_ADDR, vec![Register::GCONFIG4, BitFlags::GMODE]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::GSTATUS], vec![BitFlags::GVALID]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::GFLVL], vec![2]),
+        I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::GFIFO_U],
+            vec![110, 90, 100, 100, 110, 90, 100, 100],
+        ),
+        I2cTrans::write(DEV_ADDR, vec![Register::GCONFIG4, 0]),
+    ]);
+    trans.extend(gesture_offsets_trans(10, 0x80 | 10, 0, 0));
+    trans
+}
+
+fn white_trans() -> Vec<I2cTrans> {
+    vec![
+        I2cTrans::write_read(DEV_ADDR, vec![Register::STATUS], vec![BitFlags::AVALID]),
+        I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::CDATAL],
+            vec![0x00, 0x03, 0x00, 0x01, 0x10, 0x01, 0xF0, 0x00],
+        ),
+    ]
+}
+
+#[test]
+fn can_calibrate_proximity_offsets() {
+    let mut sensor = new(&proximity_trans());
+    let offset = sensor
+        .calibrate_proximity_offsets(&mut NoopDelay::new())
+        .unwrap();
+    assert_eq!(12, offset);
+    destroy(sensor);
+}
+
+#[test]
+fn can_calibrate_gesture_offsets() {
+    let mut sensor = new(&gesture_trans());
+    let offsets = sensor
+        .calibrate_gesture_offsets(&mut NoopDelay::new())
+        .unwrap();
+    assert_eq!([10, -10, 0, 0], offsets);
+    destroy(sensor);
+}
+
+#[test]
+fn can_calibrate_all() {
+    let mut trans = proximity_trans();
+    trans.extend(gesture_trans());
+    trans.extend(white_trans());
+    let mut sensor = new(&trans);
+    let mut steps = Vec::new();
+    let blob = sensor
+        .calibrate_all(&mut NoopDelay::new(), |step| {
+            steps.push(step);
+            true
+        })
+        .unwrap();
+    assert_eq!(
+        vec![
+            CalibrationStep::ProximityOffset,
+            CalibrationStep::GestureOffset,
+            CalibrationStep::WhiteBalance
+        ],
+        steps
+    );
+    assert_eq!(
+        CalibrationBlob {
+            proximity_up_right_offset: 12,
+            proximity_down_left_offset: 12,
+            gesture_offsets: [10, -10, 0, 0],
+            white_reference: Some(LightData {
+                clear: 0x300,
+                red: 0x100,
+                green: 0x110,
+                blue: 0xF0,
+            }),
+        },
+        blob
+    );
+    destroy(sensor);
+}
+
+#[test]
+fn can_skip_calibration_steps() {
+    let mut sensor = new(&proximity_trans());
+    let blob = sensor
+        .calibrate_all(&mut NoopDelay::new(), |step| {
+            step == CalibrationStep::ProximityOffset
+        })
+        .unwrap();
+    assert_eq!(12, blob.proximity_up_right_offset);
+    assert_eq!([0; 4], blob.gesture_offsets);
+    assert_eq!(None, blob.white_reference);
+    destroy(sensor);
+}
+
+#[test]
+fn can_apply_calibration() {
+    let mut trans = vec![I2cTrans::write(
+        DEV_ADDR,
+        vec![Register::POFFSET_UR, 12, 13],
+    )];
+    trans.extend(gesture_offsets_trans(1, 2, 3, 4));
+    let mut sensor = new(&trans);
+    let blob = CalibrationBlob {
+        proximity_up_right_offset: 12,
+        proximity_down_left_offset: 13,
+        gesture_offsets: [1, 2, 3, 4],
+        white_reference: None,
+    };
+    sensor.apply_calibration(&blob).unwrap();
+    destroy(sensor);
+}
@@ -13,7 +13,10 @@ impl Register {
     pub const AIHTL: u8 = 0x86;
     pub const PILT: u8 = 0x89;
     pub const PIHT: u8 = 0x8B;
+    pub const PERS: u8 = 0x8C;
     pub const CONFIG1: u8 = 0x8D;
+    pub const PPULSE: u8 = 0x8E;
+    pub const CONTROL: u8 = 0x8F;
     pub const CONFIG2: u8 = 0x90;
     pub const ID: u8 = 0x92;
     pub const STATUS: u8 = 0x93;
@@ -24,13 +27,17 @@ impl Register {
     pub const PDATA: u8 = 0x9C;
     pub const POFFSET_UR: u8 = 0x9D;
     pub const POFFSET_DL: u8 = 0x9E;
+    pub const CONFIG3: u8 = 0x9F;
     pub const GPENTH: u8 = 0xA0;
     pub const GPEXTH: u8 = 0xA1;
     pub const GCONFIG1: u8 = 0xA2;
+    pub const GCONFIG2: u8 = 0xA3;
     pub const GOFFSET_U: u8 = 0xA4;
     pub const GOFFSET_D: u8 = 0xA5;
+    pub const GPULSE: u8 = 0xA6;
     pub const GOFFSET_L: u8 = 0xA7;
     pub const GOFFSET_R: u8 = 0xA9;
+    pub const GCONFIG3: u8 = 0xAA;
     pub const GCONFIG4: u8 = 0xAB;
     pub const GFLVL: u8 = 0xAE;
     pub const GSTATUS: u8 = 0xAF;
@@ -54,8 +61,14 @@ impl BitFlags {
     pub const WLONG: u8 = 1 << 1;
     pub const CPSIEN: u8 = 1 << 6;
     pub const PSIEN: u8 = 1 << 7;
+    pub const SAI: u8 = 1 << 4;
     pub const AVALID: u8 = 1;
     pub const PVALID: u8 = 1 << 1;
+    pub const GINT: u8 = 1 << 2;
+    pub const AINT: u8 = 1 << 4;
+    pub const PINT: u8 = 1 << 5;
+    pub const PGSAT: u8 = 1 << 6;
+    pub const CPSAT: u8 = 1 << 7;
     pub const GMODE: u8 = 1;
     pub const GIEN: u8 = 1 << 1;
     pub const GVALID: u8 = 1;
@@ -123,6 +136,36 @@ macro_rules! read_test {
     };
 }
 
+#[macro_export]
+macro_rules! invalid_input_test {
+    ($name:ident, $method:ident $(,$arg:expr)*) => {
+        #[test]
+        fn $name() {
+            let mut sensor = new(&[]);
+            match sensor.$method($( $arg ),*) {
+                Err(Error::InvalidInputData) => (),
+                _ => panic!("InvalidInputData not returned."),
+            }
+            destroy(sensor);
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! invalid_parameter_test {
+    ($name:ident, $method:ident $(,$arg:expr)*) => {
+        #[test]
+        fn $name() {
+            let mut sensor = new(&[]);
+            match sensor.$method($( $arg ),*) {
+                Err(Error::InvalidParameter) => (),
+                _ => panic!("InvalidParameter not returned."),
+            }
+            destroy(sensor);
+        }
+    };
+}
+
 #[macro_export]
 macro_rules! assert_would_block {
     ($result: expr) => {
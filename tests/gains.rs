@@ -0,0 +1,41 @@
+extern crate apds9960;
+use apds9960::{GestureGain, LedBoost, LedDrive, LightGain, ProximityGain};
+use std::convert::TryFrom;
+
+#[test]
+fn can_convert_proximity_gain() {
+    assert_eq!(Ok(ProximityGain::X1), ProximityGain::try_from(0));
+    assert_eq!(Ok(ProximityGain::X8), ProximityGain::try_from(3));
+    assert_eq!(Err(4), ProximityGain::try_from(4));
+    assert_eq!(2, u8::from(ProximityGain::X4));
+}
+
+#[test]
+fn can_convert_light_gain() {
+    assert_eq!(Ok(LightGain::X4), LightGain::try_from(1));
+    assert_eq!(Ok(LightGain::X64), LightGain::try_from(3));
+    assert_eq!(Err(0xFF), LightGain::try_from(0xFF));
+    assert_eq!(2, u8::from(LightGain::X16));
+}
+
+#[test]
+fn can_convert_gesture_gain() {
+    assert_eq!(Ok(GestureGain::X2), GestureGain::try_from(1));
+    assert_eq!(Err(5), GestureGain::try_from(5));
+    assert_eq!(3, u8::from(GestureGain::X8));
+}
+
+#[test]
+fn can_convert_led_drive() {
+    assert_eq!(Ok(LedDrive::Ma100), LedDrive::try_from(0));
+    assert_eq!(Ok(LedDrive::Ma12_5), LedDrive::try_from(3));
+    assert_eq!(Err(4), LedDrive::try_from(4));
+    assert_eq!(1, u8::from(LedDrive::Ma50));
+}
+
+#[test]
+fn can_convert_led_boost() {
+    assert_eq!(Ok(LedBoost::Percent200), LedBoost::try_from(2));
+    assert_eq!(Err(7), LedBoost::try_from(7));
+    assert_eq!(3, u8::from(LedBoost::Percent300));
+}
@@ -1,9 +1,13 @@
 extern crate apds9960;
-use apds9960::GestureDataThreshold;
+use apds9960::{
+    DefaultGestureDecoder, Error, Gesture, GestureConfig, GestureDataThreshold,
+    GestureDecodeParams, GestureDecoder, GestureGain, GestureResult, GestureSmoother,
+    GestureStatus, GestureWaitTime, LedDrive, RepeatGestureDetector, Rotation,
+};
 extern crate embedded_hal_mock as hal;
-use hal::eh1::i2c::Transaction as I2cTrans;
+use hal::eh1::{delay::NoopDelay, i2c::Transaction as I2cTrans};
 mod common;
-use common::{destroy, new, BitFlags, Register, DEV_ADDR};
+use common::{destroy, new, BitFlags, Register, DEFAULT_CONFIG2, DEV_ADDR};
 
 write_test!(can_enable_gesture, enable_gesture, ENABLE, BitFlags::GEN);
 write_test!(can_disable_gesture, disable_gesture, ENABLE, 0);
@@ -14,6 +18,48 @@ write_test!(
     BitFlags::GMODE
 );
 write_test!(can_disable_gesture_mode, disable_gesture_mode, GCONFIG4, 0);
+write_test!(
+    can_set_gesture_mode_true,
+    set_gesture_mode,
+    GCONFIG4,
+    BitFlags::GMODE,
+    true
+);
+write_test!(
+    can_set_gesture_mode_false,
+    set_gesture_mode,
+    GCONFIG4,
+    0,
+    false
+);
+read_test!(
+    can_get_gesture_mode_high,
+    get_gesture_mode,
+    true,
+    GCONFIG4,
+    BitFlags::GMODE | BitFlags::GIEN
+);
+read_test!(
+    can_get_gesture_mode_low,
+    get_gesture_mode,
+    false,
+    GCONFIG4,
+    BitFlags::GIEN
+);
+
+#[test]
+fn get_gesture_mode_updates_cache() {
+    let trans = [
+        I2cTrans::write(DEV_ADDR, vec![Register::GCONFIG4, BitFlags::GMODE]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::GCONFIG4], vec![0]),
+        I2cTrans::write(DEV_ADDR, vec![Register::GCONFIG4, BitFlags::GIEN]),
+    ];
+    let mut sensor = new(&trans);
+    sensor.enable_gesture_mode().unwrap();
+    assert!(!sensor.get_gesture_mode().unwrap());
+    sensor.enable_gesture_interrupts().unwrap();
+    destroy(sensor);
+}
 write_test!(
     en_gesture_int,
     enable_gesture_interrupts,
@@ -21,12 +67,59 @@ write_test!(
     BitFlags::GIEN
 );
 write_test!(dis_gesture_int, disable_gesture_interrupts, GCONFIG4, 0);
+#[test]
+fn can_clear_gesture_interrupt() {
+    let trans = [
+        I2cTrans::write(DEV_ADDR, vec![Register::GCONFIG4, BitFlags::GMODE]),
+        I2cTrans::write(
+            DEV_ADDR,
+            vec![Register::GCONFIG4, BitFlags::GMODE | BitFlags::GIEN],
+        ),
+        I2cTrans::write(
+            DEV_ADDR,
+            vec![
+                Register::GCONFIG4,
+                BitFlags::GMODE | BitFlags::GIEN | BitFlags::GFIFO_CLR,
+            ],
+        ),
+        I2cTrans::write(DEV_ADDR, vec![Register::GCONFIG4, BitFlags::GIEN]),
+    ];
+    let mut sensor = new(&trans);
+    sensor.enable_gesture_mode().unwrap();
+    sensor.enable_gesture_interrupts().unwrap();
+    sensor.clear_gesture_interrupt().unwrap();
+    // GFIFO_CLR is not written again.
+    sensor.disable_gesture_mode().unwrap();
+    destroy(sensor);
+}
+
 write_test!(
     can_clear_gesture_fifo,
     clear_gesture_fifo,
     GCONFIG4,
     BitFlags::GFIFO_CLR
 );
+
+#[test]
+fn clear_gesture_fifo_keeps_cache_consistent() {
+    let trans = [
+        I2cTrans::write(DEV_ADDR, vec![Register::GCONFIG4, BitFlags::GMODE]),
+        I2cTrans::write(
+            DEV_ADDR,
+            vec![Register::GCONFIG4, BitFlags::GMODE | BitFlags::GFIFO_CLR],
+        ),
+        I2cTrans::write(
+            DEV_ADDR,
+            vec![Register::GCONFIG4, BitFlags::GMODE | BitFlags::GIEN],
+        ),
+    ];
+    let mut sensor = new(&trans);
+    sensor.enable_gesture_mode().unwrap();
+    sensor.clear_gesture_fifo().unwrap();
+    // GFIFO_CLR is not written again.
+    sensor.enable_gesture_interrupts().unwrap();
+    destroy(sensor);
+}
 write_test!(
     can_set_gprox_entry_th,
     set_gesture_proximity_entry_threshold,
@@ -42,18 +135,82 @@ write_test!(
     0xAB
 );
 
+#[test]
+fn can_set_gprox_thresholds() {
+    let trans = [I2cTrans::write(DEV_ADDR, vec![Register::GPENTH, 40, 30])];
+    let mut sensor = new(&trans);
+    sensor.set_gesture_proximity_thresholds(40, 30).unwrap();
+    destroy(sensor);
+}
+
+#[test]
+fn can_set_equal_gprox_thresholds() {
+    let trans = [I2cTrans::write(DEV_ADDR, vec![Register::GPENTH, 40, 40])];
+    let mut sensor = new(&trans);
+    sensor.set_gesture_proximity_thresholds(40, 40).unwrap();
+    destroy(sensor);
+}
+
+invalid_input_test!(
+    cannot_set_gprox_exit_above_entry,
+    set_gesture_proximity_thresholds,
+    30,
+    40
+);
+
 write_test!(set_goffset_u, set_gesture_up_offset, GOFFSET_U, 55, 55);
 write_test!(set_goffset_d, set_gesture_down_offset, GOFFSET_D, 55, 55);
 write_test!(set_goffset_l, set_gesture_left_offset, GOFFSET_L, 55, 55);
 write_test!(set_goffset_r, set_gesture_right_offset, GOFFSET_R, 55, 55);
+write_test!(
+    set_goffset_u_plus1,
+    set_gesture_up_offset,
+    GOFFSET_U,
+    0x01,
+    1
+);
+write_test!(
+    set_goffset_d_plus127,
+    set_gesture_down_offset,
+    GOFFSET_D,
+    0x7F,
+    127
+);
+write_test!(
+    set_goffset_l_minus1,
+    set_gesture_left_offset,
+    GOFFSET_L,
+    0x81,
+    -1
+);
+write_test!(
+    set_goffset_r_minus127,
+    set_gesture_right_offset,
+    GOFFSET_R,
+    0xFF,
+    -127
+);
+
+#[test]
+fn can_get_goffsets() {
+    let trans = [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::GOFFSET_U], vec![55]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::GOFFSET_D], vec![0x80 | 56]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::GOFFSET_L], vec![0x7F]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::GOFFSET_R], vec![0x81]),
+    ];
+    let mut sensor = new(&trans);
+    assert_eq!((55, -56, 127, -1), sensor.get_gesture_offsets().unwrap());
+    destroy(sensor);
+}
 
 #[test]
 fn can_set_goffsets() {
     let trans = [
         I2cTrans::write(DEV_ADDR, vec![Register::GOFFSET_U, 55]),
-        I2cTrans::write(DEV_ADDR, vec![Register::GOFFSET_D, i8::from(-56) as u8]),
+        I2cTrans::write(DEV_ADDR, vec![Register::GOFFSET_D, 0x80 | 56]),
         I2cTrans::write(DEV_ADDR, vec![Register::GOFFSET_L, 100]),
-        I2cTrans::write(DEV_ADDR, vec![Register::GOFFSET_R, i8::from(-101) as u8]),
+        I2cTrans::write(DEV_ADDR, vec![Register::GOFFSET_R, 0x80 | 101]),
     ];
     let mut sensor = new(&trans);
     sensor.set_gesture_offsets(55, -56, 100, -101).unwrap();
@@ -74,6 +231,20 @@ read_test!(
     GSTATUS,
     0
 );
+read_test!(
+    can_read_gesture_available,
+    is_gesture_available,
+    true,
+    GSTATUS,
+    BitFlags::GVALID
+);
+read_test!(
+    can_read_gesture_not_available,
+    is_gesture_available,
+    false,
+    GSTATUS,
+    0
+);
 read_test!(can_read_gfifolvl, read_gesture_data_level, 15, GFLVL, 15);
 read_test!(
     can_read_g_overfl,
@@ -90,6 +261,32 @@ read_test!(
     0
 );
 
+#[test]
+fn can_read_gesture_status() {
+    let trans = [
+        I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::GFLVL],
+            vec![12, BitFlags::GVALID | BitFlags::GFOV],
+        ),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::GFLVL], vec![0, 0]),
+    ];
+    let mut sensor = new(&trans);
+    assert_eq!(
+        GestureStatus {
+            level: 12,
+            valid: true,
+            overflow: true,
+        },
+        sensor.read_gesture_status().unwrap()
+    );
+    assert_eq!(
+        GestureStatus::default(),
+        sensor.read_gesture_status().unwrap()
+    );
+    destroy(sensor);
+}
+
 macro_rules! set_gdata_level_th_test {
     ($name:ident, $variant:ident, $value:expr) => {
         write_test!(
@@ -110,6 +307,35 @@ set_gdata_level_th_test!(
     BitFlags::GFIFOTH1 | BitFlags::GFIFOTH0
 );
 
+macro_rules! gfifo_int_level_roundtrip_test {
+    ($name:ident, $variant:ident, $value:expr) => {
+        #[test]
+        fn $name() {
+            let trans = [
+                I2cTrans::write(DEV_ADDR, vec![Register::GCONFIG1, $value]),
+                I2cTrans::write_read(DEV_ADDR, vec![Register::GCONFIG1], vec![$value]),
+            ];
+            let mut sensor = new(&trans);
+            sensor
+                .set_gesture_fifo_interrupt_level(GestureDataThreshold::$variant)
+                .unwrap();
+            assert_eq!(
+                GestureDataThreshold::$variant,
+                sensor.get_gesture_fifo_interrupt_level().unwrap()
+            );
+            destroy(sensor);
+        }
+    };
+}
+gfifo_int_level_roundtrip_test!(gfifo_int_level_th1, Th1, 0);
+gfifo_int_level_roundtrip_test!(gfifo_int_level_th4, Th4, BitFlags::GFIFOTH0);
+gfifo_int_level_roundtrip_test!(gfifo_int_level_th8, Th8, BitFlags::GFIFOTH1);
+gfifo_int_level_roundtrip_test!(
+    gfifo_int_level_th16,
+    Th16,
+    BitFlags::GFIFOTH1 | BitFlags::GFIFOTH0
+);
+
 macro_rules! read_data_test {
     ($name:ident, $method:ident, $expected:expr, $data_size:expr, $($reg:ident, [$($value:expr),*]),*) => {
         #[test]
@@ -187,3 +413,963 @@ fn cannot_read_gesture_if_not_valid() {
     assert_would_block!(sensor.read_gesture_data(&mut [0; 4]));
     destroy(sensor);
 }
+
+#[test]
+fn can_read_gesture_fifo_all() {
+    let trans = [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::GFLVL], vec![2]),
+        I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::GFIFO_U],
+            vec![1, 2, 3, 4, 5, 6, 7, 8],
+        ),
+    ];
+    let mut sensor = new(&trans);
+    let mut data = [0; 12];
+    let count = sensor.read_gesture_fifo_all(&mut data).unwrap();
+    assert_eq!(8, count);
+    assert_eq!([1, 2, 3, 4, 5, 6, 7, 8, 0, 0, 0, 0], data);
+    destroy(sensor);
+}
+
+#[test]
+fn read_gesture_fifo_all_bounded_by_buffer() {
+    // Only complete datasets are read so that the FIFO stays aligned.
+    let trans = [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::GFLVL], vec![4]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::GFIFO_U], vec![1, 2, 3, 4]),
+    ];
+    let mut sensor = new(&trans);
+    let mut data = [0; 6];
+    assert_eq!(4, sensor.read_gesture_fifo_all(&mut data).unwrap());
+    assert_eq!([1, 2, 3, 4, 0, 0], data);
+    destroy(sensor);
+}
+
+#[test]
+fn read_gesture_fifo_all_needs_room_for_a_dataset() {
+    let trans = [I2cTrans::write_read(
+        DEV_ADDR,
+        vec![Register::GFLVL],
+        vec![4],
+    )];
+    let mut sensor = new(&trans);
+    let mut data = [0; 3];
+    assert_eq!(0, sensor.read_gesture_fifo_all(&mut data).unwrap());
+    destroy(sensor);
+}
+
+#[test]
+fn read_gesture_fifo_all_empty() {
+    let trans = [I2cTrans::write_read(
+        DEV_ADDR,
+        vec![Register::GFLVL],
+        vec![0],
+    )];
+    let mut sensor = new(&trans);
+    let mut data = [0; 8];
+    assert_eq!(0, sensor.read_gesture_fifo_all(&mut data).unwrap());
+    destroy(sensor);
+}
+
+#[test]
+fn smoother_rejects_single_misread() {
+    let mut smoother = GestureSmoother::<3>::new();
+    assert_eq!(None, smoother.push(Gesture::Right));
+    assert_eq!(Some(Gesture::Right), smoother.push(Gesture::Right));
+    assert_eq!(Some(Gesture::Right), smoother.push(Gesture::Left));
+    assert_eq!(Some(Gesture::Right), smoother.push(Gesture::Right));
+    assert_eq!(None, smoother.push(Gesture::None));
+    assert_eq!(Some(Gesture::Right), smoother.push(Gesture::Right));
+}
+
+#[test]
+fn smoother_follows_new_majority() {
+    let mut smoother = GestureSmoother::<3>::new();
+    smoother.push(Gesture::Right);
+    smoother.push(Gesture::Right);
+    assert_eq!(Some(Gesture::Right), smoother.push(Gesture::Left));
+    assert_eq!(Some(Gesture::Left), smoother.push(Gesture::Left));
+    smoother.reset();
+    assert_eq!(None, smoother.push(Gesture::Up));
+}
+
+#[test]
+fn smoother_without_history_is_transparent() {
+    let mut smoother = GestureSmoother::<0>::new();
+    assert_eq!(Some(Gesture::Left), smoother.push(Gesture::Left));
+}
+
+#[test]
+fn repeated_gestures_increment_count() {
+    let mut detector = RepeatGestureDetector::new(500);
+    assert_eq!((Gesture::Up, 0), detector.update(Gesture::Up, 1000));
+    assert_eq!((Gesture::Up, 1), detector.update(Gesture::Up, 1300));
+    assert_eq!((Gesture::None, 0), detector.update(Gesture::None, 1400));
+    assert_eq!((Gesture::Up, 2), detector.update(Gesture::Up, 1700));
+    assert_eq!((Gesture::Up, 3), detector.update(Gesture::Up, 2200));
+}
+
+#[test]
+fn repeated_gestures_reset_on_direction_change_or_timeout() {
+    let mut detector = RepeatGestureDetector::new(500);
+    assert_eq!((Gesture::Left, 0), detector.update(Gesture::Left, 0));
+    assert_eq!((Gesture::Left, 1), detector.update(Gesture::Left, 100));
+    assert_eq!((Gesture::Right, 0), detector.update(Gesture::Right, 200));
+    assert_eq!((Gesture::Right, 0), detector.update(Gesture::Right, 800));
+}
+
+#[test]
+fn can_set_gesture_control() {
+    let gconf2 = 0b0110_1101;
+    let trans = [
+        I2cTrans::write(DEV_ADDR, vec![Register::GCONFIG2, gconf2]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::GCONFIG2], vec![gconf2]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::GCONFIG2], vec![gconf2]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::GCONFIG2], vec![gconf2]),
+    ];
+    let mut sensor = new(&trans);
+    sensor
+        .set_gesture_control(GestureGain::X8, LedDrive::Ma50, GestureWaitTime::Ms22_4)
+        .unwrap();
+    assert_eq!(GestureGain::X8, sensor.get_gesture_gain().unwrap());
+    assert_eq!(LedDrive::Ma50, sensor.get_gesture_led_drive().unwrap());
+    assert_eq!(
+        GestureWaitTime::Ms22_4 as u8,
+        sensor.get_gesture_wait_time().unwrap()
+    );
+    destroy(sensor);
+}
+
+read_test!(
+    gesture_gain_multiplier_x1,
+    get_gesture_gain_multiplier,
+    1,
+    GCONFIG2,
+    0b1001_1111
+);
+read_test!(
+    gesture_gain_multiplier_x2,
+    get_gesture_gain_multiplier,
+    2,
+    GCONFIG2,
+    0b0010_0000
+);
+read_test!(
+    gesture_gain_multiplier_x4,
+    get_gesture_gain_multiplier,
+    4,
+    GCONFIG2,
+    0b0100_0000
+);
+read_test!(
+    gesture_gain_multiplier_x8,
+    get_gesture_gain_multiplier,
+    8,
+    GCONFIG2,
+    0b0110_0000
+);
+
+fn gesture_fifo_trans(data: &[u8]) -> Vec<I2cTrans> {
+    let level = (data.len() / 4) as u8;
+    vec![
+        I2cTrans::write_read(DEV_ADDR, vec![Register::GSTATUS], vec![BitFlags::GVALID]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::GFLVL], vec![level]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::GFIFO_U], data.to_vec()),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::GSTATUS], vec![0]),
+    ]
+}
+
+#[test]
+fn full_fifo_is_read_in_one_burst() {
+    let mut data = Vec::new();
+    for i in 0..32 {
+        data.extend_from_slice(&[100 - i, 50 + i, 80, 80]);
+    }
+    let mut trans = gesture_fifo_trans(&data);
+    // The limit of 32 datasets is reached, so the FIFO is cleared instead of
+    // checking GSTATUS again.
+    trans.pop();
+    trans.push(I2cTrans::write(
+        DEV_ADDR,
+        vec![Register::GCONFIG4, BitFlags::GFIFO_CLR],
+    ));
+    assert_eq!(4, trans.len());
+    let mut sensor = new(&trans);
+    assert_eq!(Gesture::Up, sensor.decode_gesture().unwrap());
+    destroy(sensor);
+}
+
+#[test]
+fn fifo_is_read_again_once_drained() {
+    let trans = [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::GSTATUS], vec![BitFlags::GVALID]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::GFLVL], vec![1]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::GFIFO_U], vec![100, 50, 80, 80]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::GSTATUS], vec![BitFlags::GVALID]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::GFLVL], vec![1]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::GFIFO_U], vec![50, 100, 80, 80]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::GSTATUS], vec![0]),
+    ];
+    let mut sensor = new(&trans);
+    assert_eq!(Gesture::Up, sensor.decode_gesture().unwrap());
+    destroy(sensor);
+}
+
+#[test]
+fn can_decode_gesture() {
+    let trans = gesture_fifo_trans(&[100, 50, 80, 80, 50, 100, 80, 80]);
+    let mut sensor = new(&trans);
+    assert_eq!(Gesture::Up, sensor.decode_gesture().unwrap());
+    destroy(sensor);
+}
+
+macro_rules! rotation_test {
+    ($name:ident, $rotation:ident, $expected:ident) => {
+        #[test]
+        fn $name() {
+            let trans = gesture_fifo_trans(&[100, 50, 80, 80, 50, 100, 80, 80]);
+            let mut sensor = new(&trans);
+            sensor.set_rotation_enum(Rotation::$rotation);
+            assert_eq!(Gesture::$expected, sensor.decode_gesture().unwrap());
+            destroy(sensor);
+        }
+    };
+}
+rotation_test!(rotation_0_keeps_up, Deg0, Up);
+rotation_test!(rotation_90_maps_up_to_right, Deg90, Right);
+rotation_test!(rotation_180_maps_up_to_down, Deg180, Down);
+rotation_test!(rotation_270_maps_up_to_left, Deg270, Left);
+
+#[test]
+fn rotation_keeps_no_gesture() {
+    let trans = gesture_fifo_trans(&[100, 100, 100, 100, 100, 100, 100, 100]);
+    let mut sensor = new(&trans);
+    sensor.set_rotation_enum(Rotation::Deg90);
+    assert_eq!(Gesture::None, sensor.decode_gesture().unwrap());
+    destroy(sensor);
+}
+
+#[test]
+fn can_set_rotation_degrees() {
+    let mut sensor = new(&[]);
+    sensor.set_rotation(270).unwrap();
+    assert_eq!(270, sensor.rotation());
+    sensor.set_rotation_enum(Rotation::Deg90);
+    assert_eq!(90, sensor.rotation());
+    destroy(sensor);
+}
+
+#[test]
+fn cannot_set_invalid_rotation() {
+    let mut sensor = new(&[]);
+    match sensor.set_rotation(45) {
+        Err(Error::InvalidRotation) => (),
+        _ => panic!("InvalidRotation not returned."),
+    }
+    assert_eq!(0, sensor.rotation());
+    destroy(sensor);
+}
+
+#[test]
+fn gesture_fifo_overflow_is_cleared() {
+    let trans = [
+        I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::GSTATUS],
+            vec![BitFlags::GFOV | BitFlags::GVALID],
+        ),
+        I2cTrans::write(DEV_ADDR, vec![Register::GCONFIG4, BitFlags::GFIFO_CLR]),
+    ];
+    let mut sensor = new(&trans);
+    assert_eq!(Gesture::None, sensor.decode_gesture().unwrap());
+    destroy(sensor);
+}
+
+#[test]
+fn decode_gesture_timeout_gives_up_on_endless_data() {
+    let mut trans = vec![];
+    for _ in 0..2 {
+        trans.extend_from_slice(&[
+            I2cTrans::write_read(DEV_ADDR, vec![Register::GSTATUS], vec![BitFlags::GVALID]),
+            I2cTrans::write_read(DEV_ADDR, vec![Register::GFLVL], vec![1]),
+            I2cTrans::write_read(DEV_ADDR, vec![Register::GFIFO_U], vec![100, 50, 80, 80]),
+        ]);
+    }
+    trans.push(I2cTrans::write(
+        DEV_ADDR,
+        vec![Register::GCONFIG4, BitFlags::GFIFO_CLR],
+    ));
+    let mut sensor = new(&trans);
+    let gesture = sensor
+        .decode_gesture_timeout(&mut NoopDelay::new(), 2)
+        .unwrap();
+    assert_eq!(Gesture::None, gesture);
+    destroy(sensor);
+}
+
+#[test]
+fn decode_gesture_timeout_decodes_finished_gesture() {
+    let trans = gesture_fifo_trans(&[100, 50, 80, 80, 50, 100, 80, 80]);
+    let mut sensor = new(&trans);
+    let gesture = sensor
+        .decode_gesture_timeout(&mut NoopDelay::new(), 10)
+        .unwrap();
+    assert_eq!(Gesture::Up, gesture);
+    destroy(sensor);
+}
+
+#[test]
+fn can_read_gesture() {
+    let mut trans = gesture_fifo_trans(&[100, 50, 80, 80, 50, 100, 80, 80]);
+    trans.push(I2cTrans::write(
+        DEV_ADDR,
+        vec![Register::GCONFIG4, BitFlags::GFIFO_CLR],
+    ));
+    let mut sensor = new(&trans);
+    assert_eq!(Gesture::Up, sensor.read_gesture().unwrap());
+    destroy(sensor);
+}
+
+#[test]
+fn read_gesture_would_block_if_not_available() {
+    let trans = [I2cTrans::write_read(
+        DEV_ADDR,
+        vec![Register::GSTATUS],
+        vec![0],
+    )];
+    let mut sensor = new(&trans);
+    assert_would_block!(sensor.read_gesture());
+    destroy(sensor);
+}
+
+#[test]
+fn quick_swipe_is_faster_than_slow_swipe() {
+    let trans = gesture_fifo_trans(&[100, 50, 80, 80, 50, 100, 80, 80]);
+    let mut sensor = new(&trans);
+    let (gesture, quick) = sensor.decode_gesture_with_velocity().unwrap();
+    assert_eq!(Gesture::Up, gesture);
+    destroy(sensor);
+
+    let mut data = Vec::new();
+    for i in 0..10 {
+        data.extend_from_slice(&[100 - 5 * i, 50 + 5 * i, 80, 80]);
+    }
+    let trans = gesture_fifo_trans(&data);
+    let mut sensor = new(&trans);
+    let (gesture, slow) = sensor.decode_gesture_with_velocity().unwrap();
+    assert_eq!(Gesture::Up, gesture);
+    destroy(sensor);
+
+    assert_eq!(255, quick);
+    assert!(slow < quick);
+    assert!(slow > 0);
+}
+
+fn gesture_chunk_trans(data: &[u8]) -> Vec<I2cTrans> {
+    vec![
+        I2cTrans::write_read(DEV_ADDR, vec![Register::GSTATUS], vec![BitFlags::GVALID]),
+        I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::GFLVL],
+            vec![(data.len() / 4) as u8],
+        ),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::GFIFO_U], data.to_vec()),
+    ]
+}
+
+#[test]
+fn can_feed_gesture_in_chunks() {
+    let mut trans = gesture_chunk_trans(&[100, 50, 80, 80]);
+    trans.extend(gesture_chunk_trans(&[75, 75, 80, 80, 50, 100, 80, 80]));
+    trans.push(I2cTrans::write_read(
+        DEV_ADDR,
+        vec![Register::GSTATUS],
+        vec![0],
+    ));
+    trans.push(I2cTrans::write_read(
+        DEV_ADDR,
+        vec![Register::GSTATUS],
+        vec![0],
+    ));
+    let mut sensor = new(&trans);
+    assert_would_block!(sensor.feed_gesture());
+    assert_would_block!(sensor.feed_gesture());
+    assert_eq!(Gesture::Up, sensor.feed_gesture().unwrap());
+    // The buffer has been consumed.
+    assert_would_block!(sensor.feed_gesture());
+    destroy(sensor);
+}
+
+#[test]
+fn feed_gesture_clears_buffer_on_overflow() {
+    let mut trans = gesture_chunk_trans(&[100, 50, 80, 80]);
+    trans.push(I2cTrans::write_read(
+        DEV_ADDR,
+        vec![Register::GSTATUS],
+        vec![BitFlags::GVALID | BitFlags::GFOV],
+    ));
+    trans.push(I2cTrans::write(
+        DEV_ADDR,
+        vec![Register::GCONFIG4, BitFlags::GFIFO_CLR],
+    ));
+    trans.push(I2cTrans::write_read(
+        DEV_ADDR,
+        vec![Register::GSTATUS],
+        vec![0],
+    ));
+    let mut sensor = new(&trans);
+    assert_would_block!(sensor.feed_gesture());
+    assert_eq!(Gesture::None, sensor.feed_gesture().unwrap());
+    assert_would_block!(sensor.feed_gesture());
+    destroy(sensor);
+}
+
+#[test]
+fn saturated_gesture_is_reported() {
+    let trans = gesture_fifo_trans(&[255; 12]);
+    let mut sensor = new(&trans);
+    match sensor.decode_gesture() {
+        Err(nb::Error::Other(Error::GestureSaturated)) => (),
+        _ => panic!("GestureSaturated not returned."),
+    }
+    destroy(sensor);
+}
+
+#[test]
+fn few_saturated_datasets_are_dropped() {
+    let mut data = vec![255; 4];
+    data.extend_from_slice(&[100, 50, 80, 80, 50, 100, 80, 80]);
+    let trans = gesture_fifo_trans(&data);
+    let mut sensor = new(&trans);
+    assert_eq!(Gesture::Up, sensor.decode_gesture().unwrap());
+    destroy(sensor);
+}
+
+#[test]
+fn saturated_gesture_is_reported_when_fed() {
+    let mut trans = gesture_chunk_trans(&[255; 8]);
+    trans.extend(gesture_chunk_trans(&[100, 50, 80, 80]));
+    trans.push(I2cTrans::write_read(
+        DEV_ADDR,
+        vec![Register::GSTATUS],
+        vec![0],
+    ));
+    let mut sensor = new(&trans);
+    assert_would_block!(sensor.feed_gesture());
+    assert_would_block!(sensor.feed_gesture());
+    match sensor.feed_gesture() {
+        Err(nb::Error::Other(Error::GestureSaturated)) => (),
+        _ => panic!("GestureSaturated not returned."),
+    }
+    destroy(sensor);
+}
+
+#[test]
+fn strong_swipe_has_high_confidence() {
+    // Up/down ratio change: 78 -> -78
+    let trans = gesture_fifo_trans(&[250, 30, 80, 80, 30, 250, 80, 80]);
+    let mut sensor = new(&trans);
+    let expected = GestureResult {
+        gesture: Gesture::Up,
+        confidence: 74,
+        datasets: 2,
+    };
+    assert_eq!(expected, sensor.decode_gesture_detailed().unwrap());
+    destroy(sensor);
+}
+
+#[test]
+fn marginal_swipe_has_low_confidence() {
+    // Left/right ratio change: -17 -> 17
+    let trans = gesture_fifo_trans(&[80, 80, 70, 100, 80, 80, 80, 80, 80, 80, 100, 70]);
+    let mut sensor = new(&trans);
+    let expected = GestureResult {
+        gesture: Gesture::Right,
+        confidence: 2,
+        datasets: 3,
+    };
+    assert_eq!(expected, sensor.decode_gesture_detailed().unwrap());
+    destroy(sensor);
+}
+
+#[test]
+fn no_gesture_has_no_confidence() {
+    let trans = gesture_fifo_trans(&[100, 100, 80, 80, 100, 100, 80, 80]);
+    let mut sensor = new(&trans);
+    let expected = GestureResult {
+        gesture: Gesture::None,
+        confidence: 0,
+        datasets: 2,
+    };
+    assert_eq!(expected, sensor.decode_gesture_detailed().unwrap());
+    destroy(sensor);
+}
+
+#[test]
+fn no_gesture_has_no_velocity() {
+    let trans = gesture_fifo_trans(&[100, 100, 80, 80, 100, 100, 80, 80]);
+    let mut sensor = new(&trans);
+    assert_eq!(
+        (Gesture::None, 0),
+        sensor.decode_gesture_with_velocity().unwrap()
+    );
+    destroy(sensor);
+}
+
+#[test]
+fn dataset_below_noise_floor_is_rejected() {
+    let trans = gesture_fifo_trans(&[40, 20, 30, 30, 20, 40, 30, 30]);
+    let mut sensor = new(&trans);
+    assert_eq!(Gesture::None, sensor.decode_gesture().unwrap());
+    destroy(sensor);
+}
+
+#[test]
+fn dataset_above_low_noise_floor_is_accepted() {
+    let trans = gesture_fifo_trans(&[40, 20, 30, 30, 20, 40, 30, 30]);
+    let mut sensor = new(&trans);
+    sensor.set_gesture_decode_params(GestureDecodeParams {
+        noise_floor: 10,
+        ..GestureDecodeParams::default()
+    });
+    assert_eq!(Gesture::Up, sensor.decode_gesture().unwrap());
+    destroy(sensor);
+}
+
+#[test]
+fn high_delta_threshold_rejects_movement() {
+    let trans = gesture_fifo_trans(&[100, 50, 80, 80, 50, 100, 80, 80]);
+    let mut sensor = new(&trans);
+    sensor.set_gesture_decode_params(GestureDecodeParams {
+        ud_threshold: 100,
+        ..GestureDecodeParams::default()
+    });
+    assert_eq!(Gesture::None, sensor.decode_gesture().unwrap());
+    destroy(sensor);
+}
+
+write_test!(
+    can_set_gesture_pulse_max,
+    set_gesture_pulse,
+    GPULSE,
+    0xFF,
+    63,
+    3
+);
+invalid_parameter_test!(cannot_set_too_many_gesture_pulses, set_gesture_pulse, 64, 0);
+invalid_parameter_test!(cannot_set_too_long_gesture_pulse, set_gesture_pulse, 0, 4);
+
+#[test]
+fn can_set_gesture_wait_time_max() {
+    let trans = [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::GCONFIG2], vec![0x40]),
+        I2cTrans::write(DEV_ADDR, vec![Register::GCONFIG2, 0x47]),
+    ];
+    let mut sensor = new(&trans);
+    sensor.set_gesture_wait_time(7).unwrap();
+    destroy(sensor);
+}
+invalid_parameter_test!(
+    cannot_set_too_long_gesture_wait_time,
+    set_gesture_wait_time,
+    8
+);
+
+read_test!(
+    can_get_gesture_pulse,
+    get_gesture_pulse,
+    (9, 3),
+    GPULSE,
+    0xC9
+);
+
+macro_rules! get_gdims_test {
+    ($name:ident, $expected:expr, $value:expr) => {
+        read_test!($name, get_gesture_dimensions, $expected, GCONFIG3, $value);
+    };
+}
+get_gdims_test!(get_gdims_all, (true, true), 0);
+get_gdims_test!(get_gdims_ud, (true, false), 0b0000_1100);
+get_gdims_test!(get_gdims_lr, (false, true), 0b0000_0011);
+get_gdims_test!(get_gdims_none, (false, false), 0b0000_1111);
+
+#[test]
+fn can_set_gesture_exit_persistence() {
+    let trans = [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::GCONFIG1], vec![0b0100_0100]),
+        I2cTrans::write(DEV_ADDR, vec![Register::GCONFIG1, 0b0100_0110]),
+    ];
+    let mut sensor = new(&trans);
+    sensor.set_gesture_exit_persistence(4).unwrap();
+    destroy(sensor);
+}
+invalid_parameter_test!(
+    cannot_set_unsupported_gesture_exit_persistence,
+    set_gesture_exit_persistence,
+    3
+);
+
+#[test]
+fn can_set_gesture_exit_mask() {
+    let trans = [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::GCONFIG1], vec![0b0100_0001]),
+        I2cTrans::write(DEV_ADDR, vec![Register::GCONFIG1, 0b0110_1001]),
+    ];
+    let mut sensor = new(&trans);
+    sensor.set_gesture_exit_mask(0b1010).unwrap();
+    destroy(sensor);
+}
+
+#[test]
+fn can_set_gesture_exit_mask_max() {
+    let trans = [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::GCONFIG1], vec![0b0100_0001]),
+        I2cTrans::write(DEV_ADDR, vec![Register::GCONFIG1, 0b0111_1101]),
+    ];
+    let mut sensor = new(&trans);
+    sensor.set_gesture_exit_mask(0x0F).unwrap();
+    destroy(sensor);
+}
+invalid_parameter_test!(
+    cannot_set_too_large_gesture_exit_mask,
+    set_gesture_exit_mask,
+    0x10
+);
+
+macro_rules! get_gexpers_test {
+    ($name:ident, $expected:expr, $value:expr) => {
+        read_test!(
+            $name,
+            get_gesture_exit_persistence,
+            $expected,
+            GCONFIG1,
+            $value
+        );
+    };
+}
+get_gexpers_test!(get_gexpers_1, 1, 0b1111_1100);
+get_gexpers_test!(get_gexpers_2, 2, 0b0000_0001);
+get_gexpers_test!(get_gexpers_4, 4, 0b0000_0010);
+get_gexpers_test!(get_gexpers_7, 7, 0b0000_0011);
+
+read_test!(
+    get_gexmsk,
+    get_gesture_exit_mask,
+    0b1010,
+    GCONFIG1,
+    0b0110_1011
+);
+
+#[test]
+fn can_enable_gesture_sensor_with_interrupts() {
+    let trans = [
+        I2cTrans::write(DEV_ADDR, vec![Register::GCONFIG2, 0x41]),
+        I2cTrans::write(DEV_ADDR, vec![Register::GPULSE, 0xC9]),
+        I2cTrans::write(DEV_ADDR, vec![Register::GPENTH, 40]),
+        I2cTrans::write(DEV_ADDR, vec![Register::GPEXTH, 30]),
+        I2cTrans::write(DEV_ADDR, vec![Register::GCONFIG4, BitFlags::GIEN]),
+        I2cTrans::write(DEV_ADDR, vec![Register::ENABLE, BitFlags::PON]),
+        I2cTrans::write(
+            DEV_ADDR,
+            vec![Register::ENABLE, BitFlags::PON | BitFlags::PEN],
+        ),
+        I2cTrans::write(
+            DEV_ADDR,
+            vec![
+                Register::ENABLE,
+                BitFlags::PON | BitFlags::PEN | BitFlags::GEN,
+            ],
+        ),
+    ];
+    let mut sensor = new(&trans);
+    sensor.enable_gesture_sensor(true).unwrap();
+    destroy(sensor);
+}
+
+#[test]
+fn can_enable_and_disable_gesture_sensor_without_interrupts() {
+    let trans = [
+        I2cTrans::write(DEV_ADDR, vec![Register::GCONFIG2, 0x41]),
+        I2cTrans::write(DEV_ADDR, vec![Register::GPULSE, 0xC9]),
+        I2cTrans::write(DEV_ADDR, vec![Register::GPENTH, 40]),
+        I2cTrans::write(DEV_ADDR, vec![Register::GPEXTH, 30]),
+        I2cTrans::write(DEV_ADDR, vec![Register::GCONFIG4, 0]),
+        I2cTrans::write(DEV_ADDR, vec![Register::ENABLE, BitFlags::PON]),
+        I2cTrans::write(
+            DEV_ADDR,
+            vec![Register::ENABLE, BitFlags::PON | BitFlags::PEN],
+        ),
+        I2cTrans::write(
+            DEV_ADDR,
+            vec![
+                Register::ENABLE,
+                BitFlags::PON | BitFlags::PEN | BitFlags::GEN,
+            ],
+        ),
+        I2cTrans::write(DEV_ADDR, vec![Register::GCONFIG4, 0]),
+        I2cTrans::write(
+            DEV_ADDR,
+            vec![Register::ENABLE, BitFlags::PON | BitFlags::PEN],
+        ),
+    ];
+    let mut sensor = new(&trans);
+    sensor.enable_gesture_sensor(false).unwrap();
+    sensor.disable_gesture_sensor().unwrap();
+    destroy(sensor);
+}
+
+#[test]
+fn can_apply_high_sensitivity_gesture_config() {
+    let trans = [
+        I2cTrans::write(DEV_ADDR, vec![Register::GPULSE, 0xCF]),
+        I2cTrans::write(DEV_ADDR, vec![Register::GCONFIG2, 0x61]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::CONFIG2], vec![DEFAULT_CONFIG2]),
+        I2cTrans::write(DEV_ADDR, vec![Register::CONFIG2, 0x31]),
+        I2cTrans::write(DEV_ADDR, vec![Register::GPENTH, 10]),
+        I2cTrans::write(DEV_ADDR, vec![Register::GPEXTH, 5]),
+    ];
+    let mut sensor = new(&trans);
+    sensor
+        .apply_gesture_config(&GestureConfig::high_sensitivity())
+        .unwrap();
+    destroy(sensor);
+}
+
+#[test]
+fn can_apply_default_gesture_config() {
+    let trans = [
+        I2cTrans::write(DEV_ADDR, vec![Register::GPULSE, 0xC9]),
+        I2cTrans::write(DEV_ADDR, vec![Register::GCONFIG2, 0x41]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::CONFIG2], vec![0x31]),
+        I2cTrans::write(DEV_ADDR, vec![Register::CONFIG2, DEFAULT_CONFIG2]),
+        I2cTrans::write(DEV_ADDR, vec![Register::GPENTH, 40]),
+        I2cTrans::write(DEV_ADDR, vec![Register::GPEXTH, 30]),
+    ];
+    let mut sensor = new(&trans);
+    sensor
+        .apply_gesture_config(&GestureConfig::default())
+        .unwrap();
+    destroy(sensor);
+}
+
+invalid_parameter_test!(
+    cannot_apply_gesture_config_with_invalid_pulses,
+    apply_gesture_config,
+    &GestureConfig {
+        pulse_count: 64,
+        ..GestureConfig::high_sensitivity()
+    }
+);
+
+struct CountingDecoder {
+    dataset_count: usize,
+}
+
+impl GestureDecoder for CountingDecoder {
+    fn decode(&mut self, datasets: &[[u8; 4]]) -> Gesture {
+        self.dataset_count = datasets.len();
+        if datasets[0][0] > datasets[0][1] {
+            Gesture::Up
+        } else {
+            Gesture::Down
+        }
+    }
+}
+
+#[test]
+fn can_decode_gesture_with_custom_decoder() {
+    // The second dataset is below the noise floor.
+    let trans = gesture_fifo_trans(&[100, 50, 80, 80, 10, 10, 10, 10, 50, 100, 80, 80]);
+    let mut sensor = new(&trans);
+    let mut decoder = CountingDecoder { dataset_count: 0 };
+    assert_eq!(
+        Gesture::Up,
+        sensor.decode_gesture_with(&mut decoder).unwrap()
+    );
+    assert_eq!(2, decoder.dataset_count);
+    destroy(sensor);
+}
+
+#[test]
+fn gesture_decoding_stops_at_max_datasets() {
+    // The FIFO is not read again once the limit is reached, it is cleared instead.
+    let trans = [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::GSTATUS], vec![BitFlags::GVALID]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::GFLVL], vec![5]),
+        I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::GFIFO_U],
+            vec![
+                100, 50, 80, 80, 90, 60, 80, 80, 80, 70, 80, 80, 70, 80, 80, 80, 60, 90, 80, 80,
+            ],
+        ),
+        I2cTrans::write(DEV_ADDR, vec![Register::GCONFIG4, BitFlags::GFIFO_CLR]),
+    ];
+    let mut sensor = new(&trans);
+    sensor.set_gesture_max_datasets(3);
+    let mut decoder = CountingDecoder { dataset_count: 0 };
+    assert_eq!(
+        Gesture::Up,
+        sensor.decode_gesture_with(&mut decoder).unwrap()
+    );
+    assert_eq!(3, decoder.dataset_count);
+    destroy(sensor);
+}
+
+#[test]
+fn saturated_datasets_past_max_datasets_are_counted() {
+    // Two usable datasets reach the limit, the three saturated ones are still counted.
+    let trans = [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::GSTATUS], vec![BitFlags::GVALID]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::GFLVL], vec![5]),
+        I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::GFIFO_U],
+            vec![
+                100, 50, 80, 80, 50, 100, 80, 80, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+                255, 255,
+            ],
+        ),
+        I2cTrans::write(DEV_ADDR, vec![Register::GCONFIG4, BitFlags::GFIFO_CLR]),
+    ];
+    let mut sensor = new(&trans);
+    sensor.set_gesture_max_datasets(2);
+    match sensor.decode_gesture() {
+        Err(nb::Error::Other(Error::GestureSaturated)) => (),
+        _ => panic!("GestureSaturated not returned."),
+    }
+    destroy(sensor);
+}
+
+#[test]
+fn gesture_max_datasets_defaults_to_fifo_size() {
+    let sensor = new(&[]);
+    assert_eq!(32, sensor.gesture_max_datasets());
+    destroy(sensor);
+}
+
+#[test]
+fn gesture_max_datasets_is_limited() {
+    let mut sensor = new(&[]);
+    sensor.set_gesture_max_datasets(0);
+    assert_eq!(2, sensor.gesture_max_datasets());
+    sensor.set_gesture_max_datasets(100);
+    assert_eq!(32, sensor.gesture_max_datasets());
+    destroy(sensor);
+}
+
+#[test]
+fn custom_decoder_result_is_rotated() {
+    let trans = gesture_fifo_trans(&[100, 50, 80, 80, 50, 100, 80, 80]);
+    let mut sensor = new(&trans);
+    sensor.set_rotation_enum(Rotation::Deg90);
+    let mut decoder = CountingDecoder { dataset_count: 0 };
+    assert_eq!(
+        Gesture::Right,
+        sensor.decode_gesture_with(&mut decoder).unwrap()
+    );
+    destroy(sensor);
+}
+
+#[test]
+fn custom_decoder_is_not_called_without_datasets() {
+    let trans = gesture_fifo_trans(&[10, 10, 10, 10]);
+    let mut sensor = new(&trans);
+    let mut decoder = CountingDecoder { dataset_count: 99 };
+    assert_eq!(
+        Gesture::None,
+        sensor.decode_gesture_with(&mut decoder).unwrap()
+    );
+    assert_eq!(99, decoder.dataset_count);
+    destroy(sensor);
+}
+
+#[test]
+fn default_decoder_matches_decode_gesture() {
+    let trans = gesture_fifo_trans(&[100, 50, 80, 80, 50, 100, 80, 80]);
+    let mut sensor = new(&trans);
+    let mut decoder = DefaultGestureDecoder::default();
+    assert_eq!(
+        Gesture::Up,
+        sensor.decode_gesture_with(&mut decoder).unwrap()
+    );
+    destroy(sensor);
+}
+
+#[test]
+fn custom_decoder_would_block_without_data() {
+    let trans = [I2cTrans::write_read(
+        DEV_ADDR,
+        vec![Register::GSTATUS],
+        vec![0],
+    )];
+    let mut sensor = new(&trans);
+    let mut decoder = CountingDecoder { dataset_count: 0 };
+    assert_would_block!(sensor.decode_gesture_with(&mut decoder));
+    destroy(sensor);
+}
+
+#[test]
+fn can_get_gesture_angle() {
+    assert_eq!(None, Gesture::None.angle());
+    assert_eq!(Some(0), Gesture::Up.angle());
+    assert_eq!(Some(90), Gesture::Right.angle());
+    assert_eq!(Some(180), Gesture::Down.angle());
+    assert_eq!(Some(270), Gesture::Left.angle());
+}
+
+#[test]
+fn decode_gesture_does_not_divide_by_zero() {
+    let trans = gesture_fifo_trans(&[0, 0, 80, 80, 0, 0, 50, 100]);
+    let mut sensor = new(&trans);
+    sensor.set_gesture_decode_params(GestureDecodeParams {
+        noise_floor: 0,
+        ..GestureDecodeParams::default()
+    });
+    assert_eq!(Gesture::Left, sensor.decode_gesture().unwrap());
+    destroy(sensor);
+}
+
+#[test]
+fn default_decoder_handles_zero_photodiode_sums() {
+    let mut decoder = DefaultGestureDecoder::default();
+    assert_eq!(Gesture::None, decoder.decode(&[[0, 0, 0, 0], [0, 0, 0, 0]]));
+    assert_eq!(Gesture::Up, decoder.decode(&[[0, 0, 0, 0], [0, 100, 0, 0]]));
+}
+
+#[test]
+fn default_decoder_handles_extreme_photodiode_values() {
+    let mut decoder = DefaultGestureDecoder::default();
+    assert_eq!(
+        Gesture::Right,
+        decoder.decode(&[[255, 0, 0, 255], [0, 255, 255, 0]])
+    );
+    assert_eq!(
+        Gesture::Left,
+        decoder.decode(&[[0, 255, 255, 0], [255, 0, 0, 255]])
+    );
+    assert_eq!(
+        Gesture::Up,
+        decoder.decode(&[[255, 0, 128, 128], [0, 255, 128, 128]])
+    );
+    assert_eq!(
+        Gesture::None,
+        decoder.decode(&[[255, 255, 255, 255], [255, 255, 255, 255]])
+    );
+}
+
+#[test]
+fn velocity_saturates_at_extreme_photodiode_values() {
+    let trans = gesture_fifo_trans(&[255, 0, 128, 128, 0, 255, 128, 128]);
+    let mut sensor = new(&trans);
+    sensor.set_gesture_decode_params(GestureDecodeParams {
+        noise_floor: 0,
+        ..GestureDecodeParams::default()
+    });
+    assert_eq!(
+        (Gesture::Up, 255),
+        sensor.decode_gesture_with_velocity().unwrap()
+    );
+    destroy(sensor);
+}
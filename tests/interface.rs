@@ -1,6 +1,12 @@
 extern crate apds9960;
+use apds9960::{
+    defaults, wtime_from_cycles, wtime_to_cycles, ConfigSnapshot, EnableFlags, Engine, Error,
+    LedBoost, Mode,
+};
+extern crate embedded_hal;
 extern crate embedded_hal_mock as hal;
-use hal::eh1::i2c::Transaction as I2cTrans;
+use embedded_hal::i2c::ErrorKind;
+use hal::eh1::{delay::NoopDelay, i2c::Transaction as I2cTrans};
 mod common;
 use common::{destroy, new, BitFlags, Register, DEFAULT_CONFIG1, DEV_ADDR};
 
@@ -15,6 +21,100 @@ write_test!(can_disable, disable, ENABLE, 0);
 
 read_test!(can_read_id, read_device_id, 0xAB, ID, 0xAB);
 
+#[test]
+fn can_read_registers() {
+    let trans = [I2cTrans::write_read(
+        DEV_ADDR,
+        vec![Register::ENABLE],
+        vec![BitFlags::PON, 0xDB, 0, 0xF6],
+    )];
+    let mut sensor = new(&trans);
+    let mut out = [0; 4];
+    sensor.read_registers(Register::ENABLE, &mut out).unwrap();
+    assert_eq!([BitFlags::PON, 0xDB, 0, 0xF6], out);
+    destroy(sensor);
+}
+
+#[test]
+fn can_write_registers() {
+    let trans = [I2cTrans::write(
+        DEV_ADDR,
+        vec![Register::AILTL, 0x34, 0x12, 0xCD, 0xAB],
+    )];
+    let mut sensor = new(&trans);
+    sensor
+        .write_registers(Register::AILTL, &[0x34, 0x12, 0xCD, 0xAB])
+        .unwrap();
+    destroy(sensor);
+}
+
+#[test]
+fn write_registers_updates_cached_values() {
+    let trans = [
+        I2cTrans::write(DEV_ADDR, vec![Register::ENABLE, BitFlags::PON, 0xDB]),
+        I2cTrans::write(
+            DEV_ADDR,
+            vec![Register::ENABLE, BitFlags::PON | BitFlags::PEN],
+        ),
+    ];
+    let mut sensor = new(&trans);
+    sensor
+        .write_registers(Register::ENABLE, &[BitFlags::PON, 0xDB])
+        .unwrap();
+    sensor.enable_proximity().unwrap();
+    destroy(sensor);
+}
+
+#[test]
+fn can_write_registers_up_to_last_register() {
+    let trans = [I2cTrans::write(
+        DEV_ADDR,
+        vec![Register::GFIFO_U, 1, 2, 3, 4],
+    )];
+    let mut sensor = new(&trans);
+    sensor
+        .write_registers(Register::GFIFO_U, &[1, 2, 3, 4])
+        .unwrap();
+    destroy(sensor);
+}
+
+invalid_input_test!(
+    cannot_read_registers_below_0x80,
+    read_registers,
+    0x7F,
+    &mut [0; 2]
+);
+invalid_input_test!(
+    cannot_read_no_registers,
+    read_registers,
+    Register::ENABLE,
+    &mut []
+);
+invalid_input_test!(
+    cannot_read_registers_past_0xff,
+    read_registers,
+    Register::GFIFO_U,
+    &mut [0; 5]
+);
+invalid_input_test!(
+    cannot_write_registers_below_0x80,
+    write_registers,
+    0x7F,
+    &[0; 2]
+);
+invalid_input_test!(
+    cannot_write_no_registers,
+    write_registers,
+    Register::ENABLE,
+    &[]
+);
+invalid_input_test!(
+    cannot_write_registers_past_0xff,
+    write_registers,
+    Register::GFIFO_U,
+    &[0; 5]
+);
+
 write_test!(can_enable_wait, enable_wait, ENABLE, BitFlags::WEN);
 write_test!(can_disable_wait, disable_wait, ENABLE, 0);
 write_test!(
@@ -28,3 +128,663 @@ write_test!(set_wtime, set_wait_time, WTIME, 0x0F, 0x0F);
 
 empty_write_test!(force_int, force_interrupt, IFORCE);
 empty_write_test!(clear_ints, clear_interrupts, AICLEAR);
+
+#[test]
+fn can_set_mode_flags() {
+    let trans = [I2cTrans::write(
+        DEV_ADDR,
+        vec![
+            Register::ENABLE,
+            BitFlags::PON | BitFlags::PEN | BitFlags::GEN,
+        ],
+    )];
+    let mut sensor = new(&trans);
+    sensor
+        .set_mode_flags(Mode::POWER_ON | Mode::PROXIMITY | Mode::GESTURE)
+        .unwrap();
+    destroy(sensor);
+}
+
+#[test]
+fn set_mode_flags_updates_enable_cache() {
+    let trans = [
+        I2cTrans::write(DEV_ADDR, vec![Register::ENABLE, BitFlags::PON]),
+        I2cTrans::write(
+            DEV_ADDR,
+            vec![Register::ENABLE, BitFlags::PON | BitFlags::AEN],
+        ),
+    ];
+    let mut sensor = new(&trans);
+    sensor.set_mode_flags(Mode::POWER_ON).unwrap();
+    sensor.enable_light().unwrap();
+    destroy(sensor);
+}
+
+#[test]
+fn can_get_mode_flags() {
+    let trans = [I2cTrans::write_read(
+        DEV_ADDR,
+        vec![Register::ENABLE],
+        vec![BitFlags::PON | BitFlags::AEN | BitFlags::AIEN],
+    )];
+    let mut sensor = new(&trans);
+    let mode = sensor.get_mode_flags().unwrap();
+    assert_eq!(Mode::POWER_ON | Mode::LIGHT | Mode::LIGHT_INTERRUPT, mode);
+    assert!(mode.contains(Mode::LIGHT));
+    assert!(!mode.contains(Mode::PROXIMITY));
+    destroy(sensor);
+}
+
+#[test]
+fn can_get_enable_flags() {
+    let trans = [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::ENABLE], vec![0]),
+        I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::ENABLE],
+            vec![BitFlags::PON | BitFlags::PEN | BitFlags::PIEN],
+        ),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::ENABLE], vec![0xFF]),
+    ];
+    let mut sensor = new(&trans);
+    assert_eq!(EnableFlags::default(), sensor.get_enable_flags().unwrap());
+    assert_eq!(
+        EnableFlags {
+            power: true,
+            proximity: true,
+            proximity_interrupt: true,
+            ..EnableFlags::default()
+        },
+        sensor.get_enable_flags().unwrap()
+    );
+    assert_eq!(
+        EnableFlags {
+            power: true,
+            als: true,
+            proximity: true,
+            wait: true,
+            als_interrupt: true,
+            proximity_interrupt: true,
+            gesture: true,
+        },
+        sensor.get_enable_flags().unwrap()
+    );
+    destroy(sensor);
+}
+
+#[test]
+fn can_convert_mode() {
+    assert_eq!(0x7F, u8::from(Mode::from(0xFF)));
+    assert_eq!(Mode::empty(), Mode::from(0));
+}
+
+write_test!(en_sai, enable_sleep_after_interrupt, CONFIG3, BitFlags::SAI);
+write_test!(dis_sai, disable_sleep_after_interrupt, CONFIG3, 0);
+read_test!(
+    is_sai_enabled,
+    is_sleep_after_interrupt_enabled,
+    true,
+    CONFIG3,
+    BitFlags::SAI
+);
+read_test!(
+    is_sai_disabled,
+    is_sleep_after_interrupt_enabled,
+    false,
+    CONFIG3,
+    0b0010_1111
+);
+
+#[test]
+fn sai_keeps_photodiode_mask() {
+    let trans = [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::CONFIG3], vec![0]),
+        I2cTrans::write(DEV_ADDR, vec![Register::CONFIG3, 0b0101]),
+        I2cTrans::write(DEV_ADDR, vec![Register::CONFIG3, BitFlags::SAI | 0b0101]),
+        I2cTrans::write(DEV_ADDR, vec![Register::CONFIG3, 0b0101]),
+    ];
+    let mut sensor = new(&trans);
+    sensor.set_proximity_photodiode_mask(0b0101).unwrap();
+    sensor.enable_sleep_after_interrupt().unwrap();
+    sensor.disable_sleep_after_interrupt().unwrap();
+    destroy(sensor);
+}
+
+/// Reads done by `refresh()`: ENABLE, PERS, CONFIG1, CONFIG2, CONFIG3, GCONF1, GCONF4.
+fn refresh_trans(values: [u8; 7]) -> Vec<I2cTrans> {
+    let registers = [
+        Register::ENABLE,
+        Register::PERS,
+        Register::CONFIG1,
+        Register::CONFIG2,
+        Register::CONFIG3,
+        Register::GCONFIG1,
+        Register::GCONFIG4,
+    ];
+    registers
+        .iter()
+        .zip(values.iter())
+        .map(|(&reg, &value)| I2cTrans::write_read(DEV_ADDR, vec![reg], vec![value]))
+        .collect()
+}
+
+#[test]
+fn can_soft_reset() {
+    let mut trans = vec![
+        I2cTrans::write(DEV_ADDR, vec![Register::ENABLE, BitFlags::PON]),
+        I2cTrans::write(
+            DEV_ADDR,
+            vec![Register::CONFIG1, DEFAULT_CONFIG1 | BitFlags::WLONG],
+        ),
+        I2cTrans::write(DEV_ADDR, vec![Register::ENABLE, 0]),
+        I2cTrans::write(DEV_ADDR, vec![Register::AICLEAR]),
+        I2cTrans::write(DEV_ADDR, vec![Register::GCONFIG4, BitFlags::GFIFO_CLR]),
+    ];
+    trans.extend(refresh_trans([
+        0,
+        0x40,
+        DEFAULT_CONFIG1 | BitFlags::WLONG,
+        0x01,
+        0,
+        0x40,
+        0,
+    ]));
+    trans.push(I2cTrans::write(
+        DEV_ADDR,
+        vec![Register::CONFIG1, DEFAULT_CONFIG1],
+    ));
+    let mut sensor = new(&trans);
+    sensor.enable().unwrap();
+    sensor.enable_wait_long().unwrap();
+    sensor.soft_reset(&mut NoopDelay::new()).unwrap();
+    sensor.disable_wait_long().unwrap();
+    destroy(sensor);
+}
+
+#[test]
+fn soft_reset_keeps_configuration() {
+    let mut trans = vec![
+        I2cTrans::write_read(DEV_ADDR, vec![Register::CONFIG2], vec![0x01]),
+        I2cTrans::write(DEV_ADDR, vec![Register::CONFIG2, 0x31]),
+        I2cTrans::write(DEV_ADDR, vec![Register::ENABLE, 0]),
+        I2cTrans::write(DEV_ADDR, vec![Register::AICLEAR]),
+        I2cTrans::write(DEV_ADDR, vec![Register::GCONFIG4, BitFlags::GFIFO_CLR]),
+    ];
+    trans.extend(refresh_trans([0, 0x40, DEFAULT_CONFIG1, 0x31, 0, 0x40, 0]));
+    // The LED boost is still set.
+    trans.push(I2cTrans::write(
+        DEV_ADDR,
+        vec![Register::CONFIG2, 0x31 | BitFlags::PSIEN],
+    ));
+    let mut sensor = new(&trans);
+    sensor.set_led_boost(LedBoost::Percent300).unwrap();
+    sensor.soft_reset(&mut NoopDelay::new()).unwrap();
+    sensor.enable_proximity_saturation_interrupts().unwrap();
+    destroy(sensor);
+}
+
+#[test]
+fn can_get_and_restore_config() {
+    let registers = [
+        (Register::ENABLE, BitFlags::PON | BitFlags::PEN),
+        (Register::ATIME, 0xF6),
+        (Register::WTIME, 0xF0),
+        (Register::PERS, 0x41),
+        (Register::CONFIG1, 0x62),
+        (Register::PPULSE, 0x87),
+        (Register::CONTROL, 0x09),
+        (Register::CONFIG2, 0x11),
+        (Register::CONFIG3, 0x05),
+        (Register::GPENTH, 40),
+        (Register::GPEXTH, 30),
+        (Register::GCONFIG1, 0x40),
+        (Register::GCONFIG2, 0x41),
+        (Register::GPULSE, 0xC9),
+        (Register::GCONFIG3, 0x00),
+        (Register::GCONFIG4, BitFlags::GIEN),
+    ];
+    let mut trans: Vec<I2cTrans> = registers
+        .iter()
+        .map(|&(reg, value)| I2cTrans::write_read(DEV_ADDR, vec![reg], vec![value]))
+        .collect();
+    trans.extend(
+        registers[1..]
+            .iter()
+            .chain(registers[..1].iter())
+            .map(|&(reg, value)| I2cTrans::write(DEV_ADDR, vec![reg, value])),
+    );
+    let mut sensor = new(&trans);
+    let snap = sensor.get_config().unwrap();
+    assert_eq!(
+        ConfigSnapshot {
+            enable: BitFlags::PON | BitFlags::PEN,
+            atime: 0xF6,
+            wtime: 0xF0,
+            pers: 0x41,
+            config1: 0x62,
+            ppulse: 0x87,
+            control: 0x09,
+            config2: 0x11,
+            config3: 0x05,
+            gpenth: 40,
+            gexth: 30,
+            gconf1: 0x40,
+            gconf2: 0x41,
+            gpulse: 0xC9,
+            gconf3: 0x00,
+            gconf4: BitFlags::GIEN,
+        },
+        snap
+    );
+    sensor.restore_config(&snap).unwrap();
+    destroy(sensor);
+}
+
+#[test]
+fn read_modify_write_setter_does_not_make_cache_drift() {
+    let trans = [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::CONFIG2], vec![0x01]),
+        I2cTrans::write(DEV_ADDR, vec![Register::CONFIG2, 0x31]),
+        // The LED boost must not be overwritten with a stale cached value.
+        I2cTrans::write(DEV_ADDR, vec![Register::CONFIG2, 0x31 | BitFlags::PSIEN]),
+    ];
+    let mut sensor = new(&trans);
+    sensor.set_led_boost(LedBoost::Percent300).unwrap();
+    sensor.enable_proximity_saturation_interrupts().unwrap();
+    destroy(sensor);
+}
+
+#[test]
+fn can_refresh_cached_registers() {
+    let trans = [
+        I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::ENABLE],
+            vec![BitFlags::PON | BitFlags::PEN],
+        ),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::PERS], vec![0x40]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::CONFIG1], vec![0x60]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::CONFIG2], vec![0x31]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::CONFIG3], vec![0]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::GCONFIG1], vec![0x40]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::GCONFIG4], vec![0]),
+        I2cTrans::write(
+            DEV_ADDR,
+            vec![
+                Register::ENABLE,
+                BitFlags::PON | BitFlags::PEN | BitFlags::WEN,
+            ],
+        ),
+        I2cTrans::write(DEV_ADDR, vec![Register::CONFIG1, 0x60 | BitFlags::WLONG]),
+        I2cTrans::write(DEV_ADDR, vec![Register::CONFIG2, 0x31 | BitFlags::PSIEN]),
+    ];
+    let mut sensor = new(&trans);
+    sensor.refresh().unwrap();
+    sensor.enable_wait().unwrap();
+    sensor.enable_wait_long().unwrap();
+    sensor.enable_proximity_saturation_interrupts().unwrap();
+    destroy(sensor);
+}
+
+#[test]
+fn can_display_errors() {
+    assert_eq!("I²C bus error", format!("{}", Error::I2C(())));
+    assert_eq!(
+        "invalid rotation value",
+        format!("{}", Error::<()>::InvalidRotation)
+    );
+    assert_eq!(
+        "timed out waiting for data",
+        format!("{}", Error::<()>::Timeout)
+    );
+    assert_eq!(
+        "invalid input data",
+        format!("{}", Error::<()>::InvalidInputData)
+    );
+    assert_eq!("interrupt pin error", format!("{}", Error::<()>::Pin));
+    assert_eq!(
+        "register 0x8E verification failed: wrote 0x87, read 0x00",
+        format!(
+            "{}",
+            Error::<()>::VerifyFailed {
+                register: 0x8E,
+                expected: 0x87,
+                actual: 0
+            }
+        )
+    );
+    assert_eq!(
+        "gesture photodiodes saturated",
+        format!("{}", Error::<()>::GestureSaturated)
+    );
+    assert_eq!(
+        "parameter out of range",
+        format!("{}", Error::<()>::InvalidParameter)
+    );
+}
+
+#[test]
+fn can_set_interrupt_persistence() {
+    let trans = [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::PERS], vec![0x11]),
+        I2cTrans::write(DEV_ADDR, vec![Register::PERS, 0x5A]),
+    ];
+    let mut sensor = new(&trans);
+    sensor.set_interrupt_persistence(0xA, 5).unwrap();
+    destroy(sensor);
+}
+
+#[test]
+fn can_set_interrupt_persistence_max() {
+    let trans = [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::PERS], vec![0x11]),
+        I2cTrans::write(DEV_ADDR, vec![Register::PERS, 0xFF]),
+    ];
+    let mut sensor = new(&trans);
+    sensor.set_interrupt_persistence(15, 15).unwrap();
+    destroy(sensor);
+}
+invalid_parameter_test!(
+    cannot_set_too_high_als_persistence,
+    set_interrupt_persistence,
+    16,
+    0
+);
+invalid_parameter_test!(
+    cannot_set_too_high_proximity_persistence,
+    set_interrupt_persistence,
+    0,
+    16
+);
+
+#[test]
+fn can_get_interrupt_persistence() {
+    let trans = [I2cTrans::write_read(
+        DEV_ADDR,
+        vec![Register::PERS],
+        vec![0x5A],
+    )];
+    let mut sensor = new(&trans);
+    assert_eq!((0xA, 5), sensor.get_interrupt_persistence().unwrap());
+    destroy(sensor);
+}
+
+/// Registers and values expected from `init()` and `init_verified()`, in order.
+const INIT_REGISTERS: [(u8, u8); 28] = [
+    (Register::PPULSE, defaults::PPULSE),
+    (Register::POFFSET_UR, defaults::POFFSET_UR),
+    (Register::POFFSET_DL, defaults::POFFSET_DL),
+    (Register::PILT, defaults::PILT),
+    (Register::PIHT, defaults::PIHT),
+    (Register::ATIME, defaults::ATIME),
+    (Register::AILTL, 0xFF),
+    (Register::AILTL + 1, 0xFF),
+    (Register::AIHTL, 0),
+    (Register::AIHTL + 1, 0),
+    (Register::PERS, defaults::PERS),
+    (Register::WTIME, defaults::WTIME),
+    (Register::CONFIG1, defaults::CONFIG1),
+    (Register::CONFIG2, defaults::CONFIG2),
+    (Register::CONFIG3, defaults::CONFIG3),
+    (Register::GPENTH, defaults::GPENTH),
+    (Register::GPEXTH, defaults::GEXTH),
+    (Register::GCONFIG1, defaults::GCONF1),
+    (Register::GCONFIG2, defaults::GCONF2),
+    (Register::GPULSE, defaults::GPULSE),
+    (Register::GCONFIG3, defaults::GCONF3),
+    (Register::GCONFIG4, defaults::GCONFIG4),
+    (Register::GOFFSET_U, defaults::GOFFSET),
+    (Register::GOFFSET_D, defaults::GOFFSET),
+    (Register::GOFFSET_L, defaults::GOFFSET),
+    (Register::GOFFSET_R, defaults::GOFFSET),
+    (Register::CONTROL, defaults::CONTROL),
+    (Register::ENABLE, BitFlags::PON),
+];
+
+#[test]
+fn init_writes_defaults() {
+    let mut trans = vec![
+        I2cTrans::write(DEV_ADDR, vec![]),
+        I2cTrans::write(DEV_ADDR, vec![Register::ENABLE, 0]),
+    ];
+    for &(register, value) in INIT_REGISTERS.iter() {
+        trans.push(I2cTrans::write(DEV_ADDR, vec![register, value]));
+    }
+    let mut sensor = new(&trans);
+    sensor.init().unwrap();
+    destroy(sensor);
+    assert_eq!(0x87, defaults::PPULSE);
+    assert_eq!(0x41, defaults::GCONF2);
+    assert_eq!(0x09, defaults::CONTROL);
+}
+
+fn write_and_verify(register: u8, value: u8) -> [I2cTrans; 2] {
+    [
+        I2cTrans::write(DEV_ADDR, vec![register, value]),
+        I2cTrans::write_read(DEV_ADDR, vec![register], vec![value]),
+    ]
+}
+
+#[test]
+fn init_verified_reads_back_defaults() {
+    let mut trans = vec![
+        I2cTrans::write(DEV_ADDR, vec![]),
+        I2cTrans::write(DEV_ADDR, vec![Register::ENABLE, 0]),
+    ];
+    for &(register, value) in INIT_REGISTERS.iter() {
+        trans.extend_from_slice(&write_and_verify(register, value));
+    }
+    let mut sensor = new(&trans);
+    sensor.init_verified().unwrap();
+    destroy(sensor);
+}
+
+#[test]
+fn can_shutdown() {
+    let trans = [
+        I2cTrans::write(
+            DEV_ADDR,
+            vec![Register::ENABLE, BitFlags::PON | BitFlags::PEN],
+        ),
+        I2cTrans::write(DEV_ADDR, vec![Register::ENABLE, BitFlags::PON]),
+        I2cTrans::write(DEV_ADDR, vec![Register::AICLEAR]),
+        I2cTrans::write(DEV_ADDR, vec![Register::GCONFIG4, BitFlags::GFIFO_CLR]),
+        I2cTrans::write(DEV_ADDR, vec![Register::ENABLE, 0]),
+    ];
+    let mut sensor = new(&trans);
+    sensor
+        .set_mode_flags(Mode::POWER_ON | Mode::PROXIMITY)
+        .unwrap();
+    sensor.shutdown().unwrap();
+    destroy(sensor);
+}
+
+#[test]
+fn release_shuts_down() {
+    let trans = [
+        I2cTrans::write(DEV_ADDR, vec![Register::ENABLE, BitFlags::PON]),
+        I2cTrans::write(DEV_ADDR, vec![Register::AICLEAR]),
+        I2cTrans::write(DEV_ADDR, vec![Register::GCONFIG4, BitFlags::GFIFO_CLR]),
+        I2cTrans::write(DEV_ADDR, vec![Register::ENABLE, 0]),
+    ];
+    let sensor = new(&trans);
+    sensor.release().done();
+}
+
+#[test]
+fn release_returns_bus_on_error() {
+    let trans = [
+        I2cTrans::write(DEV_ADDR, vec![Register::ENABLE, BitFlags::PON])
+            .with_error(ErrorKind::Other),
+    ];
+    let sensor = new(&trans);
+    sensor.release().done();
+}
+
+#[test]
+fn can_reset_to_defaults() {
+    // Power-on reset values from the datasheet. All other registers reset to 0.
+    let por_value = |register| match register {
+        Register::ATIME | Register::WTIME => 0xFF,
+        Register::CONFIG1 | Register::PPULSE | Register::GPULSE => 0x40,
+        Register::CONFIG2 => 0x01,
+        _ => 0,
+    };
+    let registers = [
+        Register::ENABLE,
+        Register::ATIME,
+        Register::WTIME,
+        Register::AILTL,
+        Register::AILTL + 1,
+        Register::AIHTL,
+        Register::AIHTL + 1,
+        Register::PILT,
+        Register::PIHT,
+        Register::PERS,
+        Register::CONFIG1,
+        Register::PPULSE,
+        Register::CONTROL,
+        Register::CONFIG2,
+        Register::POFFSET_UR,
+        Register::POFFSET_DL,
+        Register::CONFIG3,
+        Register::GPENTH,
+        Register::GPEXTH,
+        Register::GCONFIG1,
+        Register::GCONFIG2,
+        Register::GOFFSET_U,
+        Register::GOFFSET_D,
+        Register::GPULSE,
+        Register::GOFFSET_L,
+        Register::GOFFSET_R,
+        Register::GCONFIG3,
+        Register::GCONFIG4,
+    ];
+    let mut trans: Vec<_> = registers
+        .iter()
+        .map(|&register| I2cTrans::write(DEV_ADDR, vec![register, por_value(register)]))
+        .collect();
+    // Cached values are reset as well.
+    trans.push(I2cTrans::write(
+        DEV_ADDR,
+        vec![Register::CONFIG1, DEFAULT_CONFIG1 | BitFlags::WLONG],
+    ));
+    trans.push(I2cTrans::write(
+        DEV_ADDR,
+        vec![Register::ENABLE, BitFlags::PON],
+    ));
+    let mut sensor = new(&trans);
+    sensor.reset_to_defaults().unwrap();
+    sensor.enable_wait_long().unwrap();
+    sensor.enable().unwrap();
+    destroy(sensor);
+}
+
+#[test]
+fn init_verified_fails_on_mismatch() {
+    let trans = [
+        I2cTrans::write(DEV_ADDR, vec![]),
+        I2cTrans::write(DEV_ADDR, vec![Register::ENABLE, 0]),
+        I2cTrans::write(DEV_ADDR, vec![Register::PPULSE, defaults::PPULSE]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::PPULSE], vec![0x07]),
+    ];
+    let mut sensor = new(&trans);
+    match sensor.init_verified() {
+        Err(Error::VerifyFailed {
+            register: Register::PPULSE,
+            expected: 0x87,
+            actual: 0x07,
+        }) => (),
+        _ => panic!("VerifyFailed not returned."),
+    }
+    destroy(sensor);
+}
+
+read_test!(is_powered_true, is_powered, true, ENABLE, BitFlags::PON);
+read_test!(is_powered_false, is_powered, false, ENABLE, 0xFE);
+
+macro_rules! is_engine_enabled_test {
+    ($name:ident, $engine:ident, $expected:expr, $value:expr) => {
+        #[test]
+        fn $name() {
+            let trans = [I2cTrans::write_read(
+                DEV_ADDR,
+                vec![Register::ENABLE],
+                vec![$value],
+            )];
+            let mut sensor = new(&trans);
+            assert_eq!(
+                $expected,
+                sensor.is_engine_enabled(Engine::$engine).unwrap()
+            );
+            destroy(sensor);
+        }
+    };
+}
+is_engine_enabled_test!(prox_enabled, Proximity, true, BitFlags::PEN);
+is_engine_enabled_test!(prox_disabled, Proximity, false, !BitFlags::PEN);
+is_engine_enabled_test!(als_enabled, Als, true, BitFlags::AEN);
+is_engine_enabled_test!(als_disabled, Als, false, !BitFlags::AEN);
+is_engine_enabled_test!(gesture_enabled, Gesture, true, BitFlags::GEN);
+is_engine_enabled_test!(gesture_disabled, Gesture, false, !BitFlags::GEN);
+is_engine_enabled_test!(wait_enabled, Wait, true, BitFlags::WEN);
+is_engine_enabled_test!(wait_disabled, Wait, false, !BitFlags::WEN);
+
+read_test!(
+    get_wait_long_on,
+    get_wait_long,
+    true,
+    CONFIG1,
+    DEFAULT_CONFIG1 | BitFlags::WLONG
+);
+read_test!(
+    get_wait_long_off,
+    get_wait_long,
+    false,
+    CONFIG1,
+    DEFAULT_CONFIG1
+);
+
+#[test]
+fn can_get_wait_time_ms() {
+    let trans = [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::WTIME], vec![0xF6]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::CONFIG1], vec![DEFAULT_CONFIG1]),
+    ];
+    let mut sensor = new(&trans);
+    let ms = sensor.get_wait_time_ms().unwrap();
+    assert!((ms - 27.8).abs() < 1e-3, "{}", ms);
+    destroy(sensor);
+}
+
+#[test]
+fn can_convert_wtime_cycles() {
+    assert_eq!(0xFF, wtime_from_cycles(1));
+    assert_eq!(0xC0, wtime_from_cycles(64));
+    assert_eq!(0, wtime_from_cycles(256));
+    assert_eq!(1, wtime_to_cycles(0xFF));
+    assert_eq!(64, wtime_to_cycles(0xC0));
+    assert_eq!(256, wtime_to_cycles(0));
+}
+
+#[test]
+fn wtime_cycles_are_clamped() {
+    assert_eq!(0xFF, wtime_from_cycles(0));
+    assert_eq!(0, wtime_from_cycles(1000));
+}
+
+#[test]
+fn can_get_long_wait_time_ms() {
+    let trans = [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::WTIME], vec![0xFF]),
+        I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::CONFIG1],
+            vec![DEFAULT_CONFIG1 | BitFlags::WLONG],
+        ),
+    ];
+    let mut sensor = new(&trans);
+    let ms = sensor.get_wait_time_ms().unwrap();
+    assert!((ms - 33.36).abs() < 1e-3, "{}", ms);
+    destroy(sensor);
+}
@@ -0,0 +1,196 @@
+extern crate apds9960;
+use apds9960::{DataReady, Error, Event, InterruptStatus, Status};
+extern crate embedded_hal_mock as hal;
+use hal::eh1::{
+    digital::{Mock as PinMock, State as PinState, Transaction as PinTrans},
+    i2c::Transaction as I2cTrans,
+    MockError,
+};
+mod common;
+use common::{destroy, new, BitFlags, Register, DEV_ADDR};
+
+#[test]
+fn can_wait_for_interrupt() {
+    let trans = [I2cTrans::write_read(
+        DEV_ADDR,
+        vec![Register::STATUS],
+        vec![BitFlags::PINT | BitFlags::PGSAT | BitFlags::PVALID],
+    )];
+    let mut sensor = new(&trans);
+    let mut pin = PinMock::new(&[
+        PinTrans::get(PinState::High),
+        PinTrans::get(PinState::High),
+        PinTrans::get(PinState::Low),
+    ]);
+    let status = sensor.wait_for_interrupt(&mut pin).unwrap();
+    assert_eq!(
+        InterruptStatus {
+            proximity: true,
+            proximity_saturation: true,
+            ..InterruptStatus::default()
+        },
+        status
+    );
+    pin.done();
+    destroy(sensor);
+}
+
+#[test]
+fn can_decode_all_interrupt_sources() {
+    let trans = [I2cTrans::write_read(
+        DEV_ADDR,
+        vec![Register::STATUS],
+        vec![0xFF],
+    )];
+    let mut sensor = new(&trans);
+    let mut pin = PinMock::new(&[PinTrans::get(PinState::Low)]);
+    let status = sensor.wait_for_interrupt(&mut pin).unwrap();
+    assert_eq!(
+        InterruptStatus {
+            light: true,
+            proximity: true,
+            gesture: true,
+            proximity_saturation: true,
+            clear_saturation: true,
+        },
+        status
+    );
+    pin.done();
+    destroy(sensor);
+}
+
+#[test]
+fn pin_error_is_returned() {
+    let mut sensor = new(&[]);
+    let mut pin = PinMock::new(&[
+        PinTrans::get(PinState::High).with_error(MockError::Io(std::io::ErrorKind::Other))
+    ]);
+    match sensor.wait_for_interrupt(&mut pin) {
+        Err(Error::Pin) => (),
+        _ => panic!("Pin error not returned."),
+    }
+    pin.done();
+    destroy(sensor);
+}
+
+#[test]
+fn can_read_status() {
+    let trans = [I2cTrans::write_read(
+        DEV_ADDR,
+        vec![Register::STATUS],
+        vec![BitFlags::PVALID | BitFlags::PINT | BitFlags::CPSAT | 0b0000_1000],
+    )];
+    let mut sensor = new(&trans);
+    let status = sensor.read_status().unwrap();
+    assert_eq!(Status::PVALID | Status::PINT | Status::CPSAT, status);
+    assert!(status.contains(Status::PVALID | Status::CPSAT));
+    assert!(!status.contains(Status::AVALID));
+    assert!(!status.contains(Status::PVALID | Status::GINT));
+    assert_eq!(0b1010_0010, status.bits());
+    destroy(sensor);
+}
+
+#[test]
+fn status_flags_match_register_bits() {
+    let all = Status::AVALID
+        | Status::PVALID
+        | Status::GINT
+        | Status::AINT
+        | Status::PINT
+        | Status::PGSAT
+        | Status::CPSAT;
+    assert_eq!(Status::from(0xFF), all);
+    assert_eq!(0b1111_0111, u8::from(all));
+    assert_eq!(Status::empty(), Status::from(0));
+}
+
+macro_rules! data_ready_test {
+    ($name:ident, $status:expr, $gstatus:expr, $proximity:expr, $als:expr, $gesture:expr) => {
+        #[test]
+        fn $name() {
+            let trans = [
+                I2cTrans::write_read(DEV_ADDR, vec![Register::STATUS], vec![$status]),
+                I2cTrans::write_read(DEV_ADDR, vec![Register::GSTATUS], vec![$gstatus]),
+            ];
+            let mut sensor = new(&trans);
+            let expected = DataReady {
+                proximity: $proximity,
+                als: $als,
+                gesture: $gesture,
+            };
+            assert_eq!(expected, sensor.data_ready().unwrap());
+            destroy(sensor);
+        }
+    };
+}
+data_ready_test!(data_ready_none, 0, 0, false, false, false);
+data_ready_test!(
+    data_ready_all,
+    BitFlags::PVALID | BitFlags::AVALID,
+    BitFlags::GVALID,
+    true,
+    true,
+    true
+);
+data_ready_test!(
+    data_ready_proximity_and_gesture,
+    BitFlags::PVALID | BitFlags::PGSAT,
+    BitFlags::GVALID | BitFlags::GFOV,
+    true,
+    false,
+    true
+);
+data_ready_test!(
+    data_ready_light_only,
+    BitFlags::AVALID | BitFlags::CPSAT | BitFlags::AINT,
+    0,
+    false,
+    true,
+    false
+);
+
+macro_rules! poll_test {
+    ($name:ident, $status:expr, $gstatus:expr, $expected:expr) => {
+        #[test]
+        fn $name() {
+            let trans = [
+                I2cTrans::write_read(DEV_ADDR, vec![Register::STATUS], vec![$status]),
+                I2cTrans::write_read(DEV_ADDR, vec![Register::GSTATUS], vec![$gstatus]),
+            ];
+            let mut sensor = new(&trans);
+            assert_eq!($expected, sensor.poll().unwrap());
+            destroy(sensor);
+        }
+    };
+}
+poll_test!(poll_none, 0, 0, None);
+poll_test!(
+    poll_gesture_first,
+    BitFlags::PVALID | BitFlags::AVALID | BitFlags::PGSAT | BitFlags::CPSAT,
+    BitFlags::GVALID,
+    Some(Event::GestureReady)
+);
+poll_test!(
+    poll_proximity_before_light,
+    BitFlags::PVALID | BitFlags::AVALID | BitFlags::PGSAT | BitFlags::CPSAT,
+    0,
+    Some(Event::ProximityReady)
+);
+poll_test!(
+    poll_light_before_saturation,
+    BitFlags::AVALID | BitFlags::PGSAT | BitFlags::CPSAT,
+    0,
+    Some(Event::LightReady)
+);
+poll_test!(
+    poll_proximity_saturation_before_color_saturation,
+    BitFlags::PGSAT | BitFlags::CPSAT,
+    0,
+    Some(Event::ProximitySaturation)
+);
+poll_test!(
+    poll_color_saturation,
+    BitFlags::CPSAT,
+    BitFlags::GFOV,
+    Some(Event::ColorSaturation)
+);
@@ -1,9 +1,12 @@
 extern crate apds9960;
-use apds9960::LightData;
+use apds9960::{
+    atime_from_cycles, atime_to_cycles, max_count_for_integration_time, Brightness, Channel, Color,
+    ColorCalibration, Error, LightConfig, LightData, LightGain, Mode, ProximityConfig, ReadMode,
+};
 extern crate embedded_hal_mock as hal;
-use hal::eh1::i2c::Transaction as I2cTrans;
+use hal::eh1::{delay::NoopDelay, i2c::Transaction as I2cTrans};
 mod common;
-use common::{destroy, new, BitFlags, Register, DEFAULT_CONFIG2, DEV_ADDR};
+use common::{destroy, new, BitFlags, Register, DEFAULT_CONFIG1, DEFAULT_CONFIG2, DEV_ADDR};
 
 write_test!(enable, enable_light, ENABLE, BitFlags::AEN);
 write_test!(disable, disable_light, ENABLE, 0);
@@ -23,6 +26,31 @@ write_test!(
 );
 write_test!(set_atime, set_light_integration_time, ATIME, 0x0F, 0x0F);
 empty_write_test!(clear_int, clear_light_interrupt, CICLEAR);
+empty_write_test!(clear_color_int, clear_color_interrupt, CICLEAR);
+
+#[test]
+fn can_read_and_clear_light_interrupt() {
+    let trans = [
+        I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::CDATAL],
+            vec![0x34, 0x12, 0x56, 0x34, 0x78, 0x56, 0x9A, 0x78],
+        ),
+        I2cTrans::write(DEV_ADDR, vec![Register::CICLEAR]),
+    ];
+    let mut sensor = new(&trans);
+    let data = sensor.read_and_clear_light_interrupt().unwrap();
+    assert_eq!(
+        LightData {
+            clear: 0x1234,
+            red: 0x3456,
+            green: 0x5678,
+            blue: 0x789A
+        },
+        data
+    );
+    destroy(sensor);
+}
 
 #[test]
 fn set_low_threshold() {
@@ -40,6 +68,38 @@ fn set_high_threshold() {
     destroy(sensor);
 }
 
+#[test]
+fn can_set_light_thresholds() {
+    let trans = [
+        I2cTrans::write(DEV_ADDR, vec![Register::AILTL, 0x34, 0x12]),
+        I2cTrans::write(DEV_ADDR, vec![Register::AIHTL, 0xCD, 0xAB]),
+    ];
+    let mut sensor = new(&trans);
+    sensor.set_light_thresholds(0x1234, 0xABCD).unwrap();
+    destroy(sensor);
+}
+
+#[test]
+fn cannot_set_light_thresholds_low_above_high() {
+    let mut sensor = new(&[]);
+    match sensor.set_light_thresholds(0xABCD, 0x1234) {
+        Err(Error::InvalidInputData) => (),
+        _ => panic!("InvalidInputData not returned."),
+    }
+    destroy(sensor);
+}
+
+#[test]
+fn can_get_light_thresholds() {
+    let trans = [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::AILTL], vec![0x34, 0x12]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::AIHTL], vec![0xCD, 0xAB]),
+    ];
+    let mut sensor = new(&trans);
+    assert_eq!((0x1234, 0xABCD), sensor.get_light_thresholds().unwrap());
+    destroy(sensor);
+}
+
 read_test!(
     is_valid,
     is_light_data_valid,
@@ -48,6 +108,20 @@ read_test!(
     BitFlags::AVALID
 );
 read_test!(is_not_valid, is_light_data_valid, false, STATUS, 0);
+read_test!(
+    is_saturated,
+    is_color_saturated,
+    true,
+    STATUS,
+    BitFlags::CPSAT
+);
+read_test!(
+    is_not_saturated,
+    is_color_saturated,
+    false,
+    STATUS,
+    BitFlags::PGSAT | BitFlags::AVALID
+);
 
 macro_rules! read_data_test {
     ($name:ident, $method:ident, $expected:expr, $($reg:ident, [$($value:expr),*]),*) => {
@@ -81,6 +155,54 @@ read_data_test!(
     [0x34, 0x12, 0x56, 0x34, 0x78, 0x56, 0x9A, 0x78]
 );
 
+read_data_test!(
+    read_rgbc_checked_saturated,
+    read_light_checked,
+    (
+        LightData {
+            clear: 0xFFFF,
+            red: 0x3456,
+            green: 0x5678,
+            blue: 0x789A
+        },
+        true
+    ),
+    STATUS,
+    [BitFlags::AVALID | BitFlags::CPSAT],
+    CDATAL,
+    [0xFF, 0xFF, 0x56, 0x34, 0x78, 0x56, 0x9A, 0x78]
+);
+
+read_data_test!(
+    read_rgbc_checked_not_saturated,
+    read_light_checked,
+    (
+        LightData {
+            clear: 0x1234,
+            red: 0x3456,
+            green: 0x5678,
+            blue: 0x789A
+        },
+        false
+    ),
+    STATUS,
+    [BitFlags::AVALID | BitFlags::PGSAT],
+    CDATAL,
+    [0x34, 0x12, 0x56, 0x34, 0x78, 0x56, 0x9A, 0x78]
+);
+
+#[test]
+fn cannot_read_light_checked_if_not_valid() {
+    let trans = [I2cTrans::write_read(
+        DEV_ADDR,
+        vec![Register::STATUS],
+        vec![BitFlags::CPSAT],
+    )];
+    let mut sensor = new(&trans);
+    assert_would_block!(sensor.read_light_checked());
+    destroy(sensor);
+}
+
 read_data_test!(
     cdata,
     read_light_clear,
@@ -118,6 +240,25 @@ read_data_test!(
     [0xCD, 0xAB]
 );
 
+macro_rules! read_channel_test {
+    ($name:ident, $channel:ident, $reg:ident) => {
+        #[test]
+        fn $name() {
+            let trans = [
+                I2cTrans::write_read(DEV_ADDR, vec![Register::STATUS], vec![BitFlags::AVALID]),
+                I2cTrans::write_read(DEV_ADDR, vec![Register::$reg], vec![0xCD, 0xAB]),
+            ];
+            let mut sensor = new(&trans);
+            assert_eq!(0xABCD, sensor.read_channel(Channel::$channel).unwrap());
+            destroy(sensor);
+        }
+    };
+}
+read_channel_test!(can_read_clear_channel, Clear, CDATAL);
+read_channel_test!(can_read_red_channel, Red, RDATAL);
+read_channel_test!(can_read_green_channel, Green, GDATAL);
+read_channel_test!(can_read_blue_channel, Blue, BDATAL);
+
 #[test]
 fn cannot_read_light_channel_if_not_valid() {
     let trans = [I2cTrans::write_read(
@@ -141,3 +282,914 @@ fn cannot_read_light_if_not_valid() {
     assert_would_block!(sensor.read_light());
     destroy(sensor);
 }
+
+#[test]
+fn auto_gain_steps_down_when_too_bright() {
+    let trans = [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::ATIME], vec![0xFF]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::CONTROL], vec![0x0A]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::STATUS], vec![BitFlags::AVALID]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::CDATAL], vec![0x01, 0x04]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::CONTROL], vec![0x0A]),
+        I2cTrans::write(DEV_ADDR, vec![Register::CONTROL, 0x09]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::STATUS], vec![BitFlags::AVALID]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::CDATAL], vec![0x2C, 0x01]),
+    ];
+    let mut sensor = new(&trans);
+    let gain = sensor
+        .auto_adjust_light_gain(&mut NoopDelay::new())
+        .unwrap();
+    assert_eq!(LightGain::X4, gain);
+    destroy(sensor);
+}
+
+#[test]
+fn auto_gain_keeps_gain_when_in_window() {
+    let trans = [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::ATIME], vec![0xFF]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::CONTROL], vec![0x01]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::STATUS], vec![BitFlags::AVALID]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::CDATAL], vec![0x00, 0x02]),
+    ];
+    let mut sensor = new(&trans);
+    let gain = sensor
+        .auto_adjust_light_gain(&mut NoopDelay::new())
+        .unwrap();
+    assert_eq!(LightGain::X4, gain);
+    destroy(sensor);
+}
+
+#[test]
+fn auto_integration_time_increases_in_dim_light() {
+    let trans = [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::ATIME], vec![0xF6]),
+        // 10 cycles: maximum count 10250
+        I2cTrans::write_read(DEV_ADDR, vec![Register::STATUS], vec![BitFlags::AVALID]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::CDATAL], vec![100, 0]),
+        I2cTrans::write(DEV_ADDR, vec![Register::ATIME, 0xEC]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::STATUS], vec![0]),
+        // 20 cycles: maximum count 20500
+        I2cTrans::write_read(DEV_ADDR, vec![Register::STATUS], vec![BitFlags::AVALID]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::CDATAL], vec![0x88, 0x13]),
+    ];
+    let mut sensor = new(&trans);
+    let atime = sensor
+        .auto_adjust_integration_time(&mut NoopDelay::new())
+        .unwrap();
+    assert_eq!(0xEC, atime);
+    destroy(sensor);
+}
+
+#[test]
+fn auto_integration_time_discards_stale_data() {
+    let trans = [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::ATIME], vec![0xF6]),
+        // 10 cycles: maximum count 10250
+        I2cTrans::write_read(DEV_ADDR, vec![Register::STATUS], vec![BitFlags::AVALID]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::CDATAL], vec![100, 0]),
+        I2cTrans::write(DEV_ADDR, vec![Register::ATIME, 0xEC]),
+        // Stale data from the 10-cycle integration would increase the time again.
+        I2cTrans::write_read(DEV_ADDR, vec![Register::STATUS], vec![BitFlags::AVALID]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::CDATAL], vec![100, 0]),
+        // 20 cycles: maximum count 20500
+        I2cTrans::write_read(DEV_ADDR, vec![Register::STATUS], vec![BitFlags::AVALID]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::CDATAL], vec![0x88, 0x13]),
+    ];
+    let mut sensor = new(&trans);
+    let atime = sensor
+        .auto_adjust_integration_time(&mut NoopDelay::new())
+        .unwrap();
+    assert_eq!(0xEC, atime);
+    destroy(sensor);
+}
+
+#[test]
+fn auto_integration_time_decreases_near_saturation() {
+    let trans = [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::ATIME], vec![0xFC]),
+        // 4 cycles: maximum count 4100
+        I2cTrans::write_read(DEV_ADDR, vec![Register::STATUS], vec![BitFlags::AVALID]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::CDATAL], vec![0x04, 0x10]),
+        I2cTrans::write(DEV_ADDR, vec![Register::ATIME, 0xFE]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::STATUS], vec![0]),
+        // 2 cycles: maximum count 2050
+        I2cTrans::write_read(DEV_ADDR, vec![Register::STATUS], vec![BitFlags::AVALID]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::CDATAL], vec![0x02, 0x08]),
+        I2cTrans::write(DEV_ADDR, vec![Register::ATIME, 0xFF]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::STATUS], vec![0]),
+        // 1 cycle: cannot be shortened anymore
+        I2cTrans::write_read(DEV_ADDR, vec![Register::STATUS], vec![BitFlags::AVALID]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::CDATAL], vec![0x01, 0x04]),
+    ];
+    let mut sensor = new(&trans);
+    let atime = sensor
+        .auto_adjust_integration_time(&mut NoopDelay::new())
+        .unwrap();
+    assert_eq!(0xFF, atime);
+    destroy(sensor);
+}
+
+#[test]
+fn quick_ambient_restores_atime() {
+    let trans = [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::ATIME], vec![0xB0]),
+        I2cTrans::write(DEV_ADDR, vec![Register::ATIME, 0xF6]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::STATUS], vec![0]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::STATUS], vec![BitFlags::AVALID]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::CDATAL], vec![100, 0]),
+        I2cTrans::write(DEV_ADDR, vec![Register::ATIME, 0xB0]),
+    ];
+    let mut sensor = new(&trans);
+    assert_eq!(800, sensor.quick_ambient(&mut NoopDelay::new()).unwrap());
+    destroy(sensor);
+}
+
+#[test]
+fn quick_ambient_discards_stale_data() {
+    let trans = [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::ATIME], vec![0xB0]),
+        I2cTrans::write(DEV_ADDR, vec![Register::ATIME, 0xF6]),
+        // Data from the cycle with the previous integration time.
+        I2cTrans::write_read(DEV_ADDR, vec![Register::STATUS], vec![BitFlags::AVALID]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::CDATAL], vec![0x60, 0xEA]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::STATUS], vec![BitFlags::AVALID]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::CDATAL], vec![100, 0]),
+        I2cTrans::write(DEV_ADDR, vec![Register::ATIME, 0xB0]),
+    ];
+    let mut sensor = new(&trans);
+    assert_eq!(800, sensor.quick_ambient(&mut NoopDelay::new()).unwrap());
+    destroy(sensor);
+}
+
+#[test]
+fn quick_ambient_restores_atime_on_timeout() {
+    let not_valid = I2cTrans::write_read(DEV_ADDR, vec![Register::STATUS], vec![0]);
+    let trans = [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::ATIME], vec![0xB6]),
+        I2cTrans::write(DEV_ADDR, vec![Register::ATIME, 0xF6]),
+        not_valid.clone(),
+        not_valid.clone(),
+        not_valid.clone(),
+        not_valid.clone(),
+        not_valid,
+        I2cTrans::write(DEV_ADDR, vec![Register::ATIME, 0xB6]),
+    ];
+    let mut sensor = new(&trans);
+    match sensor.quick_ambient(&mut NoopDelay::new()) {
+        Err(Error::Timeout) => (),
+        _ => panic!("Did not time out."),
+    }
+    destroy(sensor);
+}
+
+#[test]
+fn chromaticity_pure_red() {
+    let data = LightData {
+        clear: 100,
+        red: 100,
+        green: 0,
+        blue: 0,
+    };
+    assert_eq!((1.0, 0.0, 0.0), data.chromaticity());
+    assert_eq!([1.0, 0.0, 0.0], data.normalized());
+}
+
+#[test]
+fn chromaticity_white() {
+    let data = LightData {
+        clear: 300,
+        red: 100,
+        green: 100,
+        blue: 100,
+    };
+    let (r, g, b) = data.chromaticity();
+    assert!((r - 1.0 / 3.0).abs() < 1e-6);
+    assert_eq!(r, g);
+    assert_eq!(g, b);
+    assert_eq!([1.0 / 3.0; 3], data.normalized());
+}
+
+#[test]
+fn chromaticity_dark() {
+    let data = LightData {
+        clear: 0,
+        red: 0,
+        green: 0,
+        blue: 0,
+    };
+    assert_eq!((0.0, 0.0, 0.0), data.chromaticity());
+    assert_eq!([0.0; 3], data.normalized());
+}
+
+#[test]
+fn blocking_read_light_waits_until_valid() {
+    let trans = [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::STATUS], vec![0]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::STATUS], vec![BitFlags::AVALID]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::CDATAL], vec![0xCD, 0xAB]),
+    ];
+    let mut sensor = new(&trans);
+    sensor.set_read_mode(ReadMode::Blocking);
+    assert_eq!(0xABCD, sensor.read_light_clear().unwrap());
+    destroy(sensor);
+}
+
+#[test]
+fn can_set_color_gain() {
+    let trans = [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::CONTROL], vec![0xC4]),
+        I2cTrans::write(DEV_ADDR, vec![Register::CONTROL, 0xC6]),
+    ];
+    let mut sensor = new(&trans);
+    sensor.set_color_gain(LightGain::X16).unwrap();
+    destroy(sensor);
+}
+
+#[test]
+fn can_get_color_gain() {
+    let trans = [I2cTrans::write_read(
+        DEV_ADDR,
+        vec![Register::CONTROL],
+        vec![0xC7],
+    )];
+    let mut sensor = new(&trans);
+    assert_eq!(LightGain::X64, sensor.get_color_gain().unwrap());
+    destroy(sensor);
+}
+
+#[test]
+fn short_integration_reading_is_saturated() {
+    // ATIME 0xFF: 1 cycle, maximum count 1025
+    let data = LightData {
+        clear: 1025,
+        red: 400,
+        green: 500,
+        blue: 300,
+    };
+    assert!(data.is_saturated(0xFF));
+}
+
+#[test]
+fn saturated_color_channel_is_detected() {
+    let data = LightData {
+        clear: 1000,
+        red: 1025,
+        green: 500,
+        blue: 300,
+    };
+    assert!(data.is_saturated(0xFF));
+}
+
+#[test]
+fn reading_is_not_saturated() {
+    let data = LightData {
+        clear: 1025,
+        red: 400,
+        green: 500,
+        blue: 300,
+    };
+    assert!(!data.is_saturated(0xF6));
+    // ATIME 0: 256 cycles, maximum count clamped to 65535
+    let data = LightData {
+        clear: 65534,
+        red: 20000,
+        green: 20000,
+        blue: 20000,
+    };
+    assert!(!data.is_saturated(0));
+}
+
+#[test]
+fn can_get_max_count_for_integration_time() {
+    assert_eq!(65535, max_count_for_integration_time(0));
+    assert_eq!(1025, max_count_for_integration_time(0xFF));
+    assert_eq!(10250, max_count_for_integration_time(0xF6));
+}
+
+macro_rules! brightness_test {
+    ($name:ident, $clear:expr, $atime:expr, $gain:ident, $expected:ident) => {
+        #[test]
+        fn $name() {
+            let data = LightData {
+                clear: $clear,
+                red: 0,
+                green: 0,
+                blue: 0,
+            };
+            assert_eq!(
+                Brightness::$expected,
+                data.brightness_category($atime, LightGain::$gain)
+            );
+        }
+    };
+}
+
+// ATIME 0xDB: 37 cycles (102.86ms).
+brightness_test!(brightness_dark, 20, 0xDB, X4, Dark);
+brightness_test!(brightness_dim, 200, 0xDB, X4, Dim);
+brightness_test!(brightness_indoor, 2000, 0xDB, X4, Indoor);
+brightness_test!(brightness_bright, 20000, 0xDB, X4, Bright);
+brightness_test!(brightness_sunlight, 1000, 0xFF, X1, Sunlight);
+brightness_test!(brightness_no_light, 0, 0, X64, Dark);
+// ATIME 0xF6: 10 cycles (27.8ms).
+brightness_test!(brightness_dim_threshold, 3, 0xF6, X1, Dim);
+brightness_test!(brightness_below_dim_threshold, 2, 0xF6, X1, Dark);
+
+macro_rules! dominant_color_test {
+    ($name:ident, $red:expr, $green:expr, $blue:expr, $expected:ident) => {
+        #[test]
+        fn $name() {
+            let data = LightData {
+                clear: $red + $green + $blue,
+                red: $red,
+                green: $green,
+                blue: $blue,
+            };
+            assert_eq!(Color::$expected, data.dominant_color());
+        }
+    };
+}
+
+dominant_color_test!(dominant_red, 800, 100, 100, Red);
+dominant_color_test!(dominant_green, 100, 800, 100, Green);
+dominant_color_test!(dominant_blue, 100, 100, 800, Blue);
+dominant_color_test!(dominant_white, 300, 320, 280, White);
+dominant_color_test!(dominant_dark, 0, 0, 0, Unknown);
+dominant_color_test!(dominant_yellow, 450, 450, 100, Unknown);
+
+#[test]
+fn is_white_at_threshold() {
+    let data = LightData {
+        clear: 1000,
+        red: 300,
+        green: 300,
+        blue: 300,
+    };
+    assert!(data.is_white(0.3));
+    assert!(!data.is_white(0.31));
+}
+
+#[test]
+fn unbalanced_light_is_not_white() {
+    let data = LightData {
+        clear: 1000,
+        red: 600,
+        green: 300,
+        blue: 300,
+    };
+    assert!(!data.is_white(0.3));
+}
+
+#[test]
+fn darkness_is_not_white() {
+    let data = LightData {
+        clear: 0,
+        red: 0,
+        green: 0,
+        blue: 0,
+    };
+    assert!(!data.is_white(0.0));
+}
+
+#[test]
+fn is_black_below_threshold() {
+    let data = LightData {
+        clear: 99,
+        red: 30,
+        green: 30,
+        blue: 30,
+    };
+    assert!(data.is_black(100));
+    assert!(!data.is_black(99));
+}
+
+macro_rules! luminance_test {
+    ($name:ident, $red:expr, $green:expr, $blue:expr, $expected:expr) => {
+        #[test]
+        fn $name() {
+            let data = LightData {
+                clear: 2000,
+                red: $red,
+                green: $green,
+                blue: $blue,
+            };
+            let luminance = data.luminance();
+            assert!((luminance - $expected).abs() < 1e-2, "{}", luminance);
+        }
+    };
+}
+
+luminance_test!(luminance_red, 1000, 0, 0, 212.6);
+luminance_test!(luminance_green, 0, 1000, 0, 715.2);
+luminance_test!(luminance_blue, 0, 0, 1000, 72.2);
+luminance_test!(luminance_gray, 500, 500, 500, 500.0);
+luminance_test!(luminance_dark, 0, 0, 0, 0.0);
+
+#[test]
+fn ccm_identity_keeps_channels() {
+    let data = LightData {
+        clear: 1000,
+        red: 300,
+        green: 400,
+        blue: 200,
+    };
+    let identity = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+    assert_eq!([300.0, 400.0, 200.0], data.apply_ccm(&identity));
+}
+
+#[test]
+fn can_apply_ccm() {
+    let data = LightData {
+        clear: 1000,
+        red: 100,
+        green: 200,
+        blue: 400,
+    };
+    let matrix = [[1.5, -0.25, 0.0], [0.0, 2.0, -0.5], [0.25, 0.0, 0.5]];
+    assert_eq!([100.0, 200.0, 225.0], data.apply_ccm(&matrix));
+}
+
+macro_rules! calibration_test {
+    ($name:ident, $field:ident, $expected:expr) => {
+        #[test]
+        fn $name() {
+            let data = LightData {
+                clear: 1000,
+                red: 1000,
+                green: 1000,
+                blue: 1000,
+            };
+            let cal = ColorCalibration {
+                $field: 1.5,
+                ..ColorCalibration::identity()
+            };
+            assert_eq!($expected, data.calibrated(&cal));
+        }
+    };
+}
+
+calibration_test!(
+    can_calibrate_red,
+    r_gain,
+    LightData {
+        clear: 1000,
+        red: 1500,
+        green: 1000,
+        blue: 1000
+    }
+);
+calibration_test!(
+    can_calibrate_green,
+    g_gain,
+    LightData {
+        clear: 1000,
+        red: 1000,
+        green: 1500,
+        blue: 1000
+    }
+);
+calibration_test!(
+    can_calibrate_blue,
+    b_gain,
+    LightData {
+        clear: 1000,
+        red: 1000,
+        green: 1000,
+        blue: 1500
+    }
+);
+calibration_test!(
+    can_calibrate_clear,
+    c_gain,
+    LightData {
+        clear: 1500,
+        red: 1000,
+        green: 1000,
+        blue: 1000
+    }
+);
+
+#[test]
+fn identity_calibration_keeps_data() {
+    let data = LightData {
+        clear: 65535,
+        red: 1,
+        green: 0,
+        blue: 1234,
+    };
+    assert_eq!(data, data.calibrated(&ColorCalibration::default()));
+}
+
+#[test]
+fn calibration_saturates() {
+    let data = LightData {
+        clear: 50000,
+        red: 100,
+        green: 100,
+        blue: 100,
+    };
+    let cal = ColorCalibration {
+        r_gain: -1.0,
+        c_gain: 2.0,
+        ..ColorCalibration::identity()
+    };
+    let expected = LightData {
+        clear: 65535,
+        red: 0,
+        green: 100,
+        blue: 100,
+    };
+    assert_eq!(expected, data.calibrated(&cal));
+}
+
+#[test]
+fn can_subtract_light_data() {
+    let data = LightData {
+        clear: 1000,
+        red: 300,
+        green: 400,
+        blue: 200,
+    };
+    let baseline = LightData {
+        clear: 100,
+        red: 30,
+        green: 40,
+        blue: 20,
+    };
+    let expected = LightData {
+        clear: 900,
+        red: 270,
+        green: 360,
+        blue: 180,
+    };
+    assert_eq!(expected, data - baseline);
+}
+
+#[test]
+fn light_data_subtraction_saturates() {
+    let data = LightData {
+        clear: 10,
+        red: 0,
+        green: 5,
+        blue: 65535,
+    };
+    let baseline = LightData {
+        clear: 20,
+        red: 1,
+        green: 5,
+        blue: 0,
+    };
+    let expected = LightData {
+        clear: 0,
+        red: 0,
+        green: 0,
+        blue: 65535,
+    };
+    assert_eq!(expected, data - baseline);
+}
+
+#[test]
+fn can_scale_light_data() {
+    let data = LightData {
+        clear: 1000,
+        red: 301,
+        green: 400,
+        blue: 3,
+    };
+    let expected = LightData {
+        clear: 250,
+        red: 75,
+        green: 100,
+        blue: 1,
+    };
+    assert_eq!(expected, data.scale(0.25));
+}
+
+#[test]
+fn light_data_scaling_saturates() {
+    let data = LightData {
+        clear: 40000,
+        red: 1000,
+        green: 0,
+        blue: 20000,
+    };
+    let expected = LightData {
+        clear: 65535,
+        red: 4000,
+        green: 0,
+        blue: 65535,
+    };
+    assert_eq!(expected, data.scale(4.0));
+    let zero = LightData {
+        clear: 0,
+        red: 0,
+        green: 0,
+        blue: 0,
+    };
+    assert_eq!(zero, data.scale(-1.0));
+}
+
+#[test]
+fn can_convert_atime_cycles() {
+    assert_eq!(0xFF, atime_from_cycles(1));
+    assert_eq!(0xC0, atime_from_cycles(64));
+    assert_eq!(0, atime_from_cycles(256));
+    assert_eq!(1, atime_to_cycles(0xFF));
+    assert_eq!(64, atime_to_cycles(0xC0));
+    assert_eq!(256, atime_to_cycles(0));
+}
+
+#[test]
+fn atime_cycles_are_clamped() {
+    assert_eq!(0xFF, atime_from_cycles(0));
+    assert_eq!(0, atime_from_cycles(1000));
+}
+
+#[test]
+fn equal_light_data_is_deduplicated() {
+    let mut set = std::collections::HashSet::new();
+    let data = LightData {
+        clear: 100,
+        red: 30,
+        green: 40,
+        blue: 20,
+    };
+    set.insert(data);
+    set.insert(data);
+    set.insert(LightData { blue: 21, ..data });
+    assert_eq!(2, set.len());
+}
+
+#[test]
+fn can_estimate_ir() {
+    let data = LightData {
+        clear: 1000,
+        red: 300,
+        green: 250,
+        blue: 150,
+    };
+    assert_eq!(300, data.ir());
+}
+
+#[test]
+fn ir_estimate_does_not_underflow() {
+    let data = LightData {
+        clear: 500,
+        red: 300,
+        green: 250,
+        blue: 150,
+    };
+    assert_eq!(0, data.ir());
+    let data = LightData {
+        clear: 65535,
+        red: 65535,
+        green: 65535,
+        blue: 65535,
+    };
+    assert_eq!(0, data.ir());
+}
+
+#[test]
+fn gamma_brightens_mid_gray() {
+    let data = LightData {
+        clear: 1000,
+        red: 500,
+        green: 500,
+        blue: 500,
+    };
+    assert_eq!([128, 128, 128], data.to_rgb8_gamma(1.0));
+    // 0.5^(1 / 2.2) = 0.73
+    assert_eq!([186, 186, 186], data.to_rgb8());
+    assert_eq!(data.to_rgb8(), data.to_rgb8_gamma(2.2));
+}
+
+#[test]
+fn gamma_keeps_black_and_white() {
+    let data = LightData {
+        clear: 1000,
+        red: 1000,
+        green: 0,
+        blue: 1200,
+    };
+    assert_eq!([255, 0, 255], data.to_rgb8());
+    let data = LightData {
+        clear: 0,
+        red: 0,
+        green: 0,
+        blue: 0,
+    };
+    assert_eq!([0, 0, 0], data.to_rgb8());
+}
+
+#[test]
+fn can_enable_light_sensor_with_interrupts() {
+    let trans = [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::CONTROL], vec![0xC0]),
+        I2cTrans::write(DEV_ADDR, vec![Register::CONTROL, 0xC1]),
+        I2cTrans::write(DEV_ADDR, vec![Register::ENABLE, BitFlags::AIEN]),
+        I2cTrans::write(
+            DEV_ADDR,
+            vec![Register::ENABLE, BitFlags::PON | BitFlags::AIEN],
+        ),
+        I2cTrans::write(
+            DEV_ADDR,
+            vec![
+                Register::ENABLE,
+                BitFlags::PON | BitFlags::AIEN | BitFlags::AEN,
+            ],
+        ),
+    ];
+    let mut sensor = new(&trans);
+    sensor.enable_light_sensor(true).unwrap();
+    destroy(sensor);
+}
+
+#[test]
+fn can_enable_and_disable_light_sensor_without_interrupts() {
+    let trans = [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::CONTROL], vec![0x03]),
+        I2cTrans::write(DEV_ADDR, vec![Register::CONTROL, 0x01]),
+        I2cTrans::write(DEV_ADDR, vec![Register::ENABLE, 0]),
+        I2cTrans::write(DEV_ADDR, vec![Register::ENABLE, BitFlags::PON]),
+        I2cTrans::write(
+            DEV_ADDR,
+            vec![Register::ENABLE, BitFlags::PON | BitFlags::AEN],
+        ),
+        I2cTrans::write(
+            DEV_ADDR,
+            vec![Register::ENABLE, BitFlags::PON | BitFlags::AEN],
+        ),
+        I2cTrans::write(DEV_ADDR, vec![Register::ENABLE, BitFlags::PON]),
+    ];
+    let mut sensor = new(&trans);
+    sensor.enable_light_sensor(false).unwrap();
+    sensor.disable_light_sensor().unwrap();
+    destroy(sensor);
+}
+
+#[test]
+fn can_configure_color_meter() {
+    let trans = [
+        I2cTrans::write(
+            DEV_ADDR,
+            vec![Register::ENABLE, BitFlags::PEN | BitFlags::PIEN],
+        ),
+        I2cTrans::write(
+            DEV_ADDR,
+            vec![Register::CONFIG2, DEFAULT_CONFIG2 | BitFlags::PSIEN],
+        ),
+        I2cTrans::write(
+            DEV_ADDR,
+            vec![
+                Register::CONFIG2,
+                DEFAULT_CONFIG2 | BitFlags::PSIEN | BitFlags::CPSIEN,
+            ],
+        ),
+        I2cTrans::write(DEV_ADDR, vec![Register::GCONFIG4, BitFlags::GIEN]),
+        I2cTrans::write(DEV_ADDR, vec![Register::ENABLE, BitFlags::PON]),
+        I2cTrans::write(DEV_ADDR, vec![Register::CONFIG2, DEFAULT_CONFIG2]),
+        I2cTrans::write(DEV_ADDR, vec![Register::GCONFIG4, 0]),
+        I2cTrans::write(DEV_ADDR, vec![Register::ATIME, 0xDC]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::CONTROL], vec![0x09]),
+        I2cTrans::write(DEV_ADDR, vec![Register::CONTROL, 0x0A]),
+        I2cTrans::write(
+            DEV_ADDR,
+            vec![Register::ENABLE, BitFlags::PON | BitFlags::AEN],
+        ),
+    ];
+    let mut sensor = new(&trans);
+    sensor
+        .set_mode_flags(Mode::PROXIMITY | Mode::PROXIMITY_INTERRUPT)
+        .unwrap();
+    sensor.enable_proximity_saturation_interrupts().unwrap();
+    sensor.enable_light_saturation_interrupts().unwrap();
+    sensor.enable_gesture_interrupts().unwrap();
+    sensor.configure_color_meter(100.0, LightGain::X16).unwrap();
+    destroy(sensor);
+}
+
+#[test]
+fn can_configure_color_meter_with_longest_integration() {
+    let trans = [
+        I2cTrans::write(DEV_ADDR, vec![Register::ENABLE, BitFlags::PON]),
+        I2cTrans::write(DEV_ADDR, vec![Register::CONFIG2, DEFAULT_CONFIG2]),
+        I2cTrans::write(DEV_ADDR, vec![Register::GCONFIG4, 0]),
+        I2cTrans::write(DEV_ADDR, vec![Register::ATIME, 0]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::CONTROL], vec![0x09]),
+        I2cTrans::write(DEV_ADDR, vec![Register::CONTROL, 0x08]),
+        I2cTrans::write(
+            DEV_ADDR,
+            vec![Register::ENABLE, BitFlags::PON | BitFlags::AEN],
+        ),
+    ];
+    let mut sensor = new(&trans);
+    sensor.configure_color_meter(711.68, LightGain::X1).unwrap();
+    destroy(sensor);
+}
+
+invalid_parameter_test!(
+    cannot_configure_color_meter_with_too_short_integration,
+    configure_color_meter,
+    1.0,
+    LightGain::X1
+);
+invalid_parameter_test!(
+    cannot_configure_color_meter_with_too_long_integration,
+    configure_color_meter,
+    712.0,
+    LightGain::X1
+);
+invalid_parameter_test!(
+    cannot_configure_color_meter_with_too_short_rounded_integration,
+    configure_color_meter,
+    2.0,
+    LightGain::X1
+);
+
+#[test]
+fn can_apply_light_config() {
+    let trans = [
+        I2cTrans::write(DEV_ADDR, vec![Register::ATIME, 0xDC]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::CONTROL], vec![0x09]),
+        I2cTrans::write(DEV_ADDR, vec![Register::CONTROL, 0x0A]),
+    ];
+    let mut sensor = new(&trans);
+    sensor
+        .apply_light_config(&LightConfig {
+            gain: LightGain::X16,
+            integration_time: 0xDC,
+        })
+        .unwrap();
+    destroy(sensor);
+}
+
+#[test]
+fn can_setup_monitor() {
+    let trans = [
+        I2cTrans::write(DEV_ADDR, vec![Register::PPULSE, 0x87]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::CONTROL], vec![0x09]),
+        I2cTrans::write(DEV_ADDR, vec![Register::CONTROL, 0x09]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::CONTROL], vec![0x09]),
+        I2cTrans::write(DEV_ADDR, vec![Register::CONTROL, 0x09]),
+        I2cTrans::write(DEV_ADDR, vec![Register::WTIME, 246]),
+        I2cTrans::write(DEV_ADDR, vec![Register::CONFIG1, DEFAULT_CONFIG1]),
+        I2cTrans::write(DEV_ADDR, vec![Register::ENABLE, BitFlags::WEN]),
+        I2cTrans::write(DEV_ADDR, vec![Register::ATIME, 0xDC]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::CONTROL], vec![0x09]),
+        I2cTrans::write(DEV_ADDR, vec![Register::CONTROL, 0x0A]),
+        I2cTrans::write(
+            DEV_ADDR,
+            vec![Register::ENABLE, BitFlags::WEN | BitFlags::AEN],
+        ),
+        I2cTrans::write(
+            DEV_ADDR,
+            vec![
+                Register::ENABLE,
+                BitFlags::WEN | BitFlags::AEN | BitFlags::PEN,
+            ],
+        ),
+        I2cTrans::write(
+            DEV_ADDR,
+            vec![
+                Register::ENABLE,
+                BitFlags::PON | BitFlags::WEN | BitFlags::AEN | BitFlags::PEN,
+            ],
+        ),
+    ];
+    let mut sensor = new(&trans);
+    let light = LightConfig {
+        gain: LightGain::X16,
+        integration_time: 0xDC,
+    };
+    // Wait is enabled even though the proximity configuration does not request it.
+    sensor
+        .setup_monitor(light, ProximityConfig::default())
+        .unwrap();
+    destroy(sensor);
+}
+
+#[test]
+fn can_set_light_interrupt_persistence_max() {
+    let trans = [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::PERS], vec![0x40]),
+        I2cTrans::write(DEV_ADDR, vec![Register::PERS, 0x4F]),
+    ];
+    let mut sensor = new(&trans);
+    sensor.set_light_interrupt_persistence(15).unwrap();
+    destroy(sensor);
+}
+invalid_parameter_test!(
+    cannot_set_too_high_light_interrupt_persistence,
+    set_light_interrupt_persistence,
+    16
+);
+
+invalid_parameter_test!(
+    cannot_setup_monitor_with_invalid_proximity_pulses,
+    setup_monitor,
+    LightConfig::default(),
+    ProximityConfig {
+        pulse_count: 64,
+        ..ProximityConfig::default()
+    }
+);
@@ -0,0 +1,51 @@
+#![cfg(feature = "log")]
+extern crate apds9960;
+extern crate embedded_hal_mock as hal;
+extern crate log;
+use hal::eh1::i2c::Transaction as I2cTrans;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::sync::Mutex;
+mod common;
+use common::{destroy, new, BitFlags, Register, DEV_ADDR};
+
+struct CapturingLogger {
+    records: Mutex<Vec<(Level, String)>>,
+}
+
+impl Log for CapturingLogger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        self.records
+            .lock()
+            .unwrap()
+            .push((record.level(), format!("{}", record.args())));
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: CapturingLogger = CapturingLogger {
+    records: Mutex::new(Vec::new()),
+};
+
+#[test]
+fn register_accesses_are_logged() {
+    log::set_logger(&LOGGER).unwrap();
+    log::set_max_level(LevelFilter::Trace);
+
+    let trans = [
+        I2cTrans::write(DEV_ADDR, vec![Register::ENABLE, BitFlags::PON]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::ID], vec![0xAB]),
+    ];
+    let mut sensor = new(&trans);
+    sensor.enable().unwrap();
+    sensor.read_device_id().unwrap();
+    destroy(sensor);
+
+    let records = LOGGER.records.lock().unwrap();
+    assert!(records.contains(&(Level::Debug, "write register 0x80: 0x01".to_string())));
+    assert!(records.contains(&(Level::Trace, "read register 0x92: [AB]".to_string())));
+}
@@ -1,8 +1,12 @@
 extern crate apds9960;
+use apds9960::{
+    proximity_to_cm, BaselineTracker, Error, ProximityConfig, ProximityCurve, ProximityFilter,
+    RangeClass, ReadMode,
+};
 extern crate embedded_hal_mock as hal;
-use hal::eh1::i2c::Transaction as I2cTrans;
+use hal::eh1::{delay::NoopDelay, i2c::Transaction as I2cTrans};
 mod common;
-use common::{destroy, new, BitFlags, Register, DEFAULT_CONFIG2, DEV_ADDR};
+use common::{destroy, new, BitFlags, Register, DEFAULT_CONFIG1, DEFAULT_CONFIG2, DEV_ADDR};
 
 write_test!(can_enable, enable_proximity, ENABLE, BitFlags::PEN);
 write_test!(can_disable, disable_proximity, ENABLE, 0);
@@ -24,6 +28,38 @@ write_test!(
 write_test!(set_low_th, set_proximity_low_threshold, PILT, 0xAB, 0xAB);
 write_test!(set_high_th, set_proximity_high_threshold, PIHT, 0xAB, 0xAB);
 
+#[test]
+fn can_set_proximity_thresholds() {
+    let trans = [
+        I2cTrans::write(DEV_ADDR, vec![Register::PILT, 0x12]),
+        I2cTrans::write(DEV_ADDR, vec![Register::PIHT, 0xAB]),
+    ];
+    let mut sensor = new(&trans);
+    sensor.set_proximity_thresholds(0x12, 0xAB).unwrap();
+    destroy(sensor);
+}
+
+#[test]
+fn cannot_set_proximity_thresholds_low_above_high() {
+    let mut sensor = new(&[]);
+    match sensor.set_proximity_thresholds(0xAB, 0x12) {
+        Err(Error::InvalidInputData) => (),
+        _ => panic!("InvalidInputData not returned."),
+    }
+    destroy(sensor);
+}
+
+#[test]
+fn can_get_proximity_thresholds() {
+    let trans = [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::PILT], vec![0x12]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::PIHT], vec![0xAB]),
+    ];
+    let mut sensor = new(&trans);
+    assert_eq!((0x12, 0xAB), sensor.get_proximity_thresholds().unwrap());
+    destroy(sensor);
+}
+
 write_test!(
     set_ur_off,
     set_proximity_up_right_offset,
@@ -41,15 +77,89 @@ write_test!(
 
 #[test]
 fn can_set_poffsets() {
-    let data = vec![Register::POFFSET_UR, 55, i8::from(-56) as u8];
+    let data = vec![Register::POFFSET_UR, 55, 0x80 | 56];
     let trans = [I2cTrans::write(DEV_ADDR, data)];
     let mut sensor = new(&trans);
     sensor.set_proximity_offsets(55, -56).unwrap();
     destroy(sensor);
 }
 
+macro_rules! get_poffsets_test {
+    ($name:ident, $ur:expr, $dl:expr, $expected:expr) => {
+        #[test]
+        fn $name() {
+            let trans = [I2cTrans::write_read(
+                DEV_ADDR,
+                vec![Register::POFFSET_UR],
+                vec![$ur, $dl],
+            )];
+            let mut sensor = new(&trans);
+            assert_eq!($expected, sensor.get_proximity_offsets().unwrap());
+            destroy(sensor);
+        }
+    };
+}
+get_poffsets_test!(get_poffsets_zero, 0, 0x80, (0, 0));
+get_poffsets_test!(get_poffsets_positive, 55, 127, (55, 127));
+get_poffsets_test!(get_poffsets_negative, 0x81, 0xFF, (-1, -127));
+get_poffsets_test!(get_poffsets_mixed, 0xB8, 0x05, (-56, 5));
+
+write_test!(
+    set_ur_off_plus1,
+    set_proximity_up_right_offset,
+    POFFSET_UR,
+    0x01,
+    1
+);
+write_test!(
+    set_ur_off_plus127,
+    set_proximity_up_right_offset,
+    POFFSET_UR,
+    0x7F,
+    127
+);
+write_test!(
+    set_ur_off_minus1,
+    set_proximity_up_right_offset,
+    POFFSET_UR,
+    0x81,
+    -1
+);
+write_test!(
+    set_ur_off_minus127,
+    set_proximity_up_right_offset,
+    POFFSET_UR,
+    0xFF,
+    -127
+);
+write_test!(
+    set_ur_off_minus128,
+    set_proximity_up_right_offset,
+    POFFSET_UR,
+    0xFF,
+    -128
+);
+write_test!(
+    set_dl_off_minus1,
+    set_proximity_down_left_offset,
+    POFFSET_DL,
+    0x81,
+    -1
+);
+
 empty_write_test!(clear_int, clear_proximity_interrupt, PICLEAR);
 
+#[test]
+fn can_read_and_clear_proximity_interrupt() {
+    let trans = [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::PDATA], vec![0x42]),
+        I2cTrans::write(DEV_ADDR, vec![Register::PICLEAR]),
+    ];
+    let mut sensor = new(&trans);
+    assert_eq!(0x42, sensor.read_and_clear_proximity_interrupt().unwrap());
+    destroy(sensor);
+}
+
 read_test!(
     is_pvalid_true,
     is_proximity_data_valid,
@@ -58,6 +168,49 @@ read_test!(
     BitFlags::PVALID
 );
 read_test!(is_pvalid_false, is_proximity_data_valid, false, STATUS, 0);
+read_test!(
+    is_psat_true,
+    is_proximity_saturated,
+    true,
+    STATUS,
+    BitFlags::PGSAT
+);
+read_test!(
+    is_psat_false,
+    is_proximity_saturated,
+    false,
+    STATUS,
+    BitFlags::CPSAT | BitFlags::PVALID
+);
+
+#[test]
+fn is_psat_interrupt_when_enabled() {
+    let trans = [
+        I2cTrans::write(
+            DEV_ADDR,
+            vec![Register::CONFIG2, DEFAULT_CONFIG2 | BitFlags::PSIEN],
+        ),
+        I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::STATUS],
+            vec![BitFlags::PGSAT | BitFlags::PINT],
+        ),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::STATUS], vec![BitFlags::PINT]),
+    ];
+    let mut sensor = new(&trans);
+    sensor.enable_proximity_saturation_interrupts().unwrap();
+    assert!(sensor.is_proximity_saturation_interrupt().unwrap());
+    assert!(!sensor.is_proximity_saturation_interrupt().unwrap());
+    destroy(sensor);
+}
+
+read_test!(
+    is_not_psat_interrupt_when_disabled,
+    is_proximity_saturation_interrupt,
+    false,
+    STATUS,
+    BitFlags::PGSAT | BitFlags::PINT
+);
 
 read_test!(
     can_read_prox,
@@ -69,6 +222,36 @@ read_test!(
     0x12
 );
 
+// No STATUS read is done.
+read_test!(can_read_prox_raw, read_proximity_raw, 0x12, PDATA, 0x12);
+
+#[test]
+fn read_prox_does_not_read_light_data() {
+    // Reading the color data registers would clear AVALID.
+    let trans = [
+        I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::STATUS],
+            vec![BitFlags::PVALID | BitFlags::AVALID],
+        ),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::PDATA], vec![0x12]),
+        I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::STATUS],
+            vec![BitFlags::PVALID | BitFlags::AVALID],
+        ),
+        I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::CDATAL],
+            vec![0x34, 0x12, 0x56, 0x34, 0x78, 0x56, 0x9A, 0x78],
+        ),
+    ];
+    let mut sensor = new(&trans);
+    assert_eq!(0x12, sensor.read_proximity().unwrap());
+    assert_eq!(0x1234, sensor.read_light().unwrap().clear);
+    destroy(sensor);
+}
+
 #[test]
 fn cannot_read_prox_if_not_valid() {
     let trans = [I2cTrans::write_read(
@@ -80,3 +263,384 @@ fn cannot_read_prox_if_not_valid() {
     assert_would_block!(sensor.read_proximity());
     destroy(sensor);
 }
+
+macro_rules! range_test {
+    ($name:ident, $expected:ident, $control:expr, $config2:expr, $ppulse:expr) => {
+        read_test!(
+            $name,
+            estimated_max_range,
+            RangeClass::$expected,
+            CONTROL,
+            $control,
+            CONFIG2,
+            $config2,
+            PPULSE,
+            $ppulse
+        );
+    };
+}
+range_test!(range_high_power, Long, 0x0C, 0x31, 0xFF);
+range_test!(range_default, Medium, 0x09, DEFAULT_CONFIG2, 0x87);
+range_test!(range_low_power, Short, 0xC0, DEFAULT_CONFIG2, 0x00);
+
+#[test]
+fn filter_averages_before_full() {
+    let mut filter = ProximityFilter::<4>::new();
+    assert_eq!(10, filter.push(10));
+    assert_eq!(15, filter.push(20));
+    assert_eq!(20, filter.push(30));
+}
+
+#[test]
+fn filter_averages_over_window() {
+    let mut filter = ProximityFilter::<3>::new();
+    filter.push(10);
+    filter.push(20);
+    assert_eq!(20, filter.push(30));
+    assert_eq!(30, filter.push(40));
+    filter.push(255);
+    filter.push(255);
+    assert_eq!(255, filter.push(255));
+}
+
+#[test]
+fn can_read_filtered() {
+    let trans = [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::STATUS], vec![BitFlags::PVALID]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::PDATA], vec![20]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::STATUS], vec![BitFlags::PVALID]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::PDATA], vec![40]),
+    ];
+    let mut sensor = new(&trans);
+    let mut filter = ProximityFilter::<2>::new();
+    assert_eq!(20, filter.read_filtered(&mut sensor).unwrap());
+    assert_eq!(30, filter.read_filtered(&mut sensor).unwrap());
+    destroy(sensor);
+}
+
+#[test]
+fn blocking_read_waits_until_valid() {
+    let trans = [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::STATUS], vec![0]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::STATUS], vec![0]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::STATUS], vec![BitFlags::PVALID]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::PDATA], vec![0x12]),
+    ];
+    let mut sensor = new(&trans);
+    sensor.set_read_mode(ReadMode::Blocking);
+    assert_eq!(0x12, sensor.read_proximity().unwrap());
+    destroy(sensor);
+}
+
+#[test]
+fn timeout_read_gives_up() {
+    let trans = [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::STATUS], vec![0]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::STATUS], vec![0]),
+    ];
+    let mut sensor = new(&trans);
+    sensor.set_read_mode(ReadMode::Timeout(2));
+    match sensor.read_proximity() {
+        Err(nb::Error::Other(Error::Timeout)) => (),
+        _ => panic!("Did not time out."),
+    }
+    destroy(sensor);
+}
+
+#[test]
+fn timeout_read_succeeds_within_polls() {
+    let trans = [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::STATUS], vec![0]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::STATUS], vec![BitFlags::PVALID]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::PDATA], vec![0x12]),
+    ];
+    let mut sensor = new(&trans);
+    sensor.set_read_mode(ReadMode::Timeout(2));
+    assert_eq!(0x12, sensor.read_proximity().unwrap());
+    destroy(sensor);
+}
+
+#[test]
+fn non_blocking_is_default() {
+    let trans = [I2cTrans::write_read(
+        DEV_ADDR,
+        vec![Register::STATUS],
+        vec![0],
+    )];
+    let mut sensor = new(&trans);
+    assert_eq!(ReadMode::NonBlocking, sensor.read_mode());
+    assert_would_block!(sensor.read_proximity());
+    destroy(sensor);
+}
+
+#[test]
+fn baseline_follows_drift() {
+    let mut tracker = BaselineTracker::new(2, 20);
+    assert_eq!(0, tracker.update(10));
+    assert_eq!(10, tracker.baseline());
+    for _ in 0..30 {
+        tracker.update(20);
+    }
+    assert_eq!(19, tracker.baseline());
+    assert_eq!(1, tracker.delta(20));
+}
+
+#[test]
+fn baseline_ignores_presence() {
+    let mut tracker = BaselineTracker::new(2, 20);
+    tracker.update(10);
+    for _ in 0..30 {
+        assert_eq!(190, tracker.update(200));
+    }
+    assert_eq!(10, tracker.baseline());
+}
+
+#[test]
+fn can_read_proximity_vs_baseline() {
+    let trans = [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::STATUS], vec![BitFlags::PVALID]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::PDATA], vec![10]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::STATUS], vec![BitFlags::PVALID]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::PDATA], vec![50]),
+    ];
+    let mut sensor = new(&trans);
+    let mut tracker = BaselineTracker::new(4, 20);
+    assert_eq!(0, sensor.proximity_vs_baseline(&mut tracker).unwrap());
+    assert_eq!(40, sensor.proximity_vs_baseline(&mut tracker).unwrap());
+    destroy(sensor);
+}
+
+write_test!(
+    can_set_proximity_pulse_max,
+    set_proximity_pulse,
+    PPULSE,
+    0xFF,
+    63,
+    3
+);
+invalid_parameter_test!(
+    cannot_set_too_many_proximity_pulses,
+    set_proximity_pulse,
+    64,
+    0
+);
+invalid_parameter_test!(
+    cannot_set_too_long_proximity_pulse,
+    set_proximity_pulse,
+    0,
+    4
+);
+
+read_test!(
+    can_get_proximity_pulse,
+    get_proximity_pulse,
+    (7, 2),
+    PPULSE,
+    0x87
+);
+
+#[test]
+fn can_set_proximity_interrupt_persistence_max() {
+    let trans = [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::PERS], vec![0x01]),
+        I2cTrans::write(DEV_ADDR, vec![Register::PERS, 0xF1]),
+    ];
+    let mut sensor = new(&trans);
+    sensor.set_proximity_interrupt_persistence(15).unwrap();
+    destroy(sensor);
+}
+invalid_parameter_test!(
+    cannot_set_too_high_proximity_interrupt_persistence,
+    set_proximity_interrupt_persistence,
+    16
+);
+
+#[test]
+fn can_set_proximity_photodiode_mask_max() {
+    let trans = [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::CONFIG3], vec![0x20]),
+        I2cTrans::write(DEV_ADDR, vec![Register::CONFIG3, 0x2F]),
+    ];
+    let mut sensor = new(&trans);
+    sensor.set_proximity_photodiode_mask(0x0F).unwrap();
+    destroy(sensor);
+}
+invalid_parameter_test!(
+    cannot_set_too_large_proximity_photodiode_mask,
+    set_proximity_photodiode_mask,
+    0x10
+);
+
+#[test]
+fn can_read_proximity_blocking() {
+    let trans = [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::STATUS], vec![0]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::STATUS], vec![0]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::STATUS], vec![BitFlags::PVALID]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::PDATA], vec![0x42]),
+    ];
+    let mut sensor = new(&trans);
+    let prox = sensor
+        .read_proximity_blocking(&mut NoopDelay::new())
+        .unwrap();
+    assert_eq!(0x42, prox);
+    destroy(sensor);
+}
+
+#[test]
+fn can_read_proximity_samples() {
+    let trans = [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::STATUS], vec![BitFlags::PVALID]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::PDATA], vec![10]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::STATUS], vec![0]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::STATUS], vec![BitFlags::PVALID]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::PDATA], vec![12]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::STATUS], vec![BitFlags::PVALID]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::PDATA], vec![11]),
+    ];
+    let mut sensor = new(&trans);
+    let mut samples = [0; 3];
+    let count = sensor
+        .read_proximity_samples(&mut samples, &mut NoopDelay::new())
+        .unwrap();
+    assert_eq!(3, count);
+    assert_eq!([10, 12, 11], samples);
+    destroy(sensor);
+}
+
+fn proximity_samples_trans(samples: &[u8]) -> Vec<I2cTrans> {
+    let mut trans = Vec::new();
+    for &sample in samples {
+        trans.push(I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::STATUS],
+            vec![BitFlags::PVALID],
+        ));
+        trans.push(I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::PDATA],
+            vec![sample],
+        ));
+    }
+    trans
+}
+
+#[test]
+fn can_auto_tune_proximity_thresholds() {
+    let mut trans = proximity_samples_trans(&[10, 12, 11, 9, 10, 11, 9, 8]);
+    trans.push(I2cTrans::write(DEV_ADDR, vec![Register::PILT, 5]));
+    trans.push(I2cTrans::write(DEV_ADDR, vec![Register::PIHT, 15]));
+    let mut sensor = new(&trans);
+    let thresholds = sensor
+        .auto_tune_proximity_thresholds(&mut NoopDelay::new(), 5)
+        .unwrap();
+    assert_eq!((5, 15), thresholds);
+    destroy(sensor);
+}
+
+#[test]
+fn auto_tuned_proximity_thresholds_saturate() {
+    let mut trans = proximity_samples_trans(&[250; 8]);
+    trans.push(I2cTrans::write(DEV_ADDR, vec![Register::PILT, 0]));
+    trans.push(I2cTrans::write(DEV_ADDR, vec![Register::PIHT, 255]));
+    let mut sensor = new(&trans);
+    let thresholds = sensor
+        .auto_tune_proximity_thresholds(&mut NoopDelay::new(), 255)
+        .unwrap();
+    assert_eq!((0, 255), thresholds);
+    destroy(sensor);
+}
+
+#[test]
+fn read_proximity_samples_stops_on_timeout() {
+    let mut trans = vec![
+        I2cTrans::write_read(DEV_ADDR, vec![Register::STATUS], vec![BitFlags::PVALID]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::PDATA], vec![10]),
+    ];
+    for _ in 0..200 {
+        trans.push(I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::STATUS],
+            vec![0],
+        ));
+    }
+    let mut sensor = new(&trans);
+    let mut samples = [0; 3];
+    let count = sensor
+        .read_proximity_samples(&mut samples, &mut NoopDelay::new())
+        .unwrap();
+    assert_eq!(1, count);
+    assert_eq!([10, 0, 0], samples);
+    destroy(sensor);
+}
+
+fn assert_near(expected: f32, actual: f32) {
+    assert!(
+        (expected - actual).abs() < 1e-4,
+        "{} != {}",
+        expected,
+        actual
+    );
+}
+
+#[test]
+fn can_interpolate_proximity_distance() {
+    // Readings get higher the closer the object is.
+    let curve = ProximityCurve::new([(200, 2.0), (50, 10.0)]);
+    assert_near(2.0, proximity_to_cm(200, &curve));
+    assert_near(10.0, proximity_to_cm(50, &curve));
+    assert_near(6.0, proximity_to_cm(125, &curve));
+}
+
+#[test]
+fn can_extrapolate_proximity_distance() {
+    let curve = ProximityCurve::new([(50, 10.0), (200, 4.0)]);
+    assert_near(12.0, proximity_to_cm(0, &curve));
+    assert_near(2.0, proximity_to_cm(250, &curve));
+}
+
+#[test]
+fn can_use_multi_point_proximity_curve() {
+    let curve = ProximityCurve::new([(20, 20.0), (100, 5.0), (250, 1.0)]);
+    assert_near(12.5, proximity_to_cm(60, &curve));
+    assert_near(3.0, proximity_to_cm(175, &curve));
+}
+
+#[test]
+fn degenerate_proximity_curves() {
+    assert_near(0.0, proximity_to_cm(10, &ProximityCurve::new([])));
+    assert_near(4.0, proximity_to_cm(10, &ProximityCurve::new([(80, 4.0)])));
+    let curve = ProximityCurve::new([(80, 4.0), (80, 6.0)]);
+    assert_near(4.0, proximity_to_cm(10, &curve));
+}
+
+#[test]
+fn can_apply_low_power_proximity_config() {
+    let trans = [
+        I2cTrans::write(DEV_ADDR, vec![Register::PPULSE, 0x43]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::CONTROL], vec![0x09]),
+        I2cTrans::write(DEV_ADDR, vec![Register::CONTROL, 0x0D]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::CONTROL], vec![0x0D]),
+        I2cTrans::write(DEV_ADDR, vec![Register::CONTROL, 0x8D]),
+        I2cTrans::write(DEV_ADDR, vec![Register::WTIME, 0xDC]),
+        I2cTrans::write(
+            DEV_ADDR,
+            vec![Register::CONFIG1, DEFAULT_CONFIG1 | BitFlags::WLONG],
+        ),
+        I2cTrans::write(DEV_ADDR, vec![Register::ENABLE, BitFlags::WEN]),
+    ];
+    let mut sensor = new(&trans);
+    sensor
+        .apply_proximity_config(&ProximityConfig::low_power())
+        .unwrap();
+    destroy(sensor);
+}
+
+invalid_parameter_test!(
+    cannot_apply_proximity_config_with_invalid_pulses,
+    apply_proximity_config,
+    &ProximityConfig {
+        pulse_length: 4,
+        ..ProximityConfig::default()
+    }
+);
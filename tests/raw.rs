@@ -0,0 +1,63 @@
+#![cfg(feature = "unstable-raw")]
+extern crate apds9960;
+use apds9960::Error;
+extern crate embedded_hal_mock as hal;
+use hal::eh1::i2c::Transaction as I2cTrans;
+mod common;
+use common::{destroy, new, Register, DEV_ADDR};
+
+#[test]
+fn can_read_field() {
+    let trans = [I2cTrans::write_read(
+        DEV_ADDR,
+        vec![Register::CONTROL],
+        vec![0b0100_1001],
+    )];
+    let mut sensor = new(&trans);
+    assert_eq!(2, sensor.field(Register::CONTROL, 0b0000_1100, 2).unwrap());
+    destroy(sensor);
+}
+
+#[test]
+fn can_set_field() {
+    let trans = [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::CONTROL], vec![0b0100_1001]),
+        I2cTrans::write(DEV_ADDR, vec![Register::CONTROL, 0b0100_1101]),
+    ];
+    let mut sensor = new(&trans);
+    sensor
+        .set_field(Register::CONTROL, 0b0000_1100, 2, 0b1111_0011)
+        .unwrap();
+    destroy(sensor);
+}
+
+invalid_parameter_test!(
+    cannot_read_field_with_too_large_shift,
+    field,
+    Register::CONTROL,
+    0xFF,
+    8
+);
+invalid_parameter_test!(
+    cannot_read_field_with_mask_below_shift,
+    field,
+    Register::CONTROL,
+    0b0000_0011,
+    2
+);
+invalid_parameter_test!(
+    cannot_set_field_with_too_large_shift,
+    set_field,
+    Register::CONTROL,
+    0xFF,
+    8,
+    1
+);
+invalid_parameter_test!(
+    cannot_set_field_with_empty_mask,
+    set_field,
+    Register::CONTROL,
+    0,
+    0,
+    1
+);
@@ -0,0 +1,49 @@
+#![cfg(feature = "rgb")]
+extern crate apds9960;
+extern crate rgb;
+use apds9960::LightData;
+use rgb::RGB8;
+
+#[test]
+fn saturated_white_is_full_white() {
+    let data = LightData {
+        clear: 65535,
+        red: 65535,
+        green: 65535,
+        blue: 65535,
+    };
+    assert_eq!(RGB8::new(255, 255, 255), RGB8::from(data));
+}
+
+#[test]
+fn dim_red_is_scaled_by_clear() {
+    let data = LightData {
+        clear: 100,
+        red: 90,
+        green: 5,
+        blue: 5,
+    };
+    assert_eq!(RGB8::new(229, 12, 12), RGB8::from(data));
+}
+
+#[test]
+fn channels_above_clear_are_clamped() {
+    let data = LightData {
+        clear: 100,
+        red: 120,
+        green: 0,
+        blue: 0,
+    };
+    assert_eq!(RGB8::new(255, 0, 0), RGB8::from(data));
+}
+
+#[test]
+fn dark_is_black() {
+    let data = LightData {
+        clear: 0,
+        red: 0,
+        green: 0,
+        blue: 0,
+    };
+    assert_eq!(RGB8::new(0, 0, 0), RGB8::from(data));
+}